@@ -1,7 +1,155 @@
-use clap::Parser;
+use clap::{Parser, Subcommand, ValueEnum};
+use std::path::PathBuf;
 
 #[derive(Parser, Debug)]
-#[command(version, about, long_about = None)]
+#[command(version, about, long_about = None, args_conflicts_with_subcommands = true)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    #[command(flatten)]
+    pub run: Option<Args>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Write a random, valid puzzle input for a day
+    Gen(GenArgs),
+    /// Compare the fast and the naive solvers on generated inputs
+    Diff(DiffArgs),
+    /// Check an input against the day's format and list every problem with its line
+    Validate(ValidateArgs),
+    /// Shrink an input while a solver keeps panicking or disagreeing on it
+    Minimize(MinimizeArgs),
+    /// Time days and parts, record the timings and compare them with earlier commits
+    Bench(BenchArgs),
+}
+
+#[derive(clap::Args, Debug)]
+pub struct GenArgs {
+    #[arg(short, long)]
+    pub day: u8,
+
+    /// Seed for the generator; the same seed and size give the same input
+    #[arg(long, default_value_t = 0)]
+    pub seed: u64,
+
+    /// Rough scale of the input: lines, items, grid side or node count depending on the day
+    #[arg(long, default_value_t = 100)]
+    pub size: usize,
+
+    /// Tag of the written `inputs/dayNN_<tag>.txt`, for running it with `--input-tag`
+    #[arg(long, default_value = "gen")]
+    pub tag: String,
+
+    /// Write to this path instead of the inputs directory
+    #[arg(long)]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct DiffArgs {
+    /// Only check this day (defaults to every day)
+    #[arg(short, long)]
+    pub day: Option<u8>,
+
+    /// First seed to generate inputs from
+    #[arg(long, default_value_t = 0)]
+    pub seed: u64,
+
+    /// Number of seeds to try
+    #[arg(long, default_value_t = 100)]
+    pub runs: u64,
+
+    /// Size of the generated inputs; the naive solvers need them small
+    #[arg(long, default_value_t = 10)]
+    pub size: usize,
+
+    /// Solver checked for the day 10 joltage presses; lpsolve prints its own log
+    #[arg(long, value_enum, default_value_t = JoltageSolver::Exact)]
+    pub joltage_solver: JoltageSolver,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct ValidateArgs {
+    #[arg(short, long)]
+    pub day: u8,
+
+    #[arg(short, long, default_value = None)]
+    pub input_tag: Option<String>,
+
+    /// Check this file instead of one from the inputs directory
+    #[arg(long)]
+    pub input: Option<PathBuf>,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct MinimizeArgs {
+    #[arg(short, long)]
+    pub day: u8,
+
+    /// Only look for the failure in this part (defaults to every part)
+    #[arg(short, long)]
+    pub part: Option<u8>,
+
+    #[arg(short, long, default_value = None)]
+    pub input_tag: Option<String>,
+
+    /// Shrink this file instead of one from the inputs directory
+    #[arg(long)]
+    pub input: Option<PathBuf>,
+
+    /// Failure the shrunk input has to keep showing
+    #[arg(long, value_enum, default_value_t = Failure::Panic)]
+    pub failure: Failure,
+
+    /// Write the reproducer here instead of `inputs/dayNN_min.txt`
+    #[arg(long)]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct BenchArgs {
+    #[arg(short, long, required_unless_present = "all")]
+    pub day: Option<u8>,
+
+    /// Time every day
+    #[arg(long, conflicts_with = "day")]
+    pub all: bool,
+
+    /// Only time this part (defaults to every part)
+    #[arg(short, long)]
+    pub part: Option<u8>,
+
+    #[arg(short, long, default_value = None)]
+    pub input_tag: Option<String>,
+
+    /// Timed runs per day and part, after one warm-up run
+    #[arg(long, default_value_t = 10)]
+    pub runs: usize,
+
+    /// Compare with the latest timings of another commit and fail on regressions
+    #[arg(long)]
+    pub compare: bool,
+
+    /// Slowdown of the median, in percent, that counts as a regression if significant
+    #[arg(long, default_value_t = 10.0)]
+    pub threshold: f64,
+
+    /// History file the timings are appended to (defaults to `bench_history.tsv`)
+    #[arg(long)]
+    pub history: Option<PathBuf>,
+
+    /// Print the results as JSON instead of one summary line per part
+    #[arg(long)]
+    pub json: bool,
+
+    /// Give up on a day and part after this many seconds and report it as timed out
+    #[arg(long)]
+    pub timeout: Option<f64>,
+}
+
+#[derive(clap::Args, Debug)]
 pub struct Args {
     /// Name of the person to greet
     #[arg(short, long)]
@@ -13,4 +161,116 @@ pub struct Args {
 
     #[arg(short, long, default_value = None)]
     pub input_tag: Option<String>,
+
+    /// Solver for the day 10 joltage presses
+    #[arg(long, value_enum, default_value_t = JoltageSolver::Lp)]
+    pub joltage_solver: JoltageSolver,
+
+    /// Day 11: node the counted paths start at (defaults to the part's start node)
+    #[arg(long)]
+    pub start: Option<String>,
+
+    /// Day 11: node the counted paths end at (defaults to `out`)
+    #[arg(long)]
+    pub end: Option<String>,
+
+    /// Day 11: comma-separated nodes every counted path visits, in any order
+    #[arg(long, value_delimiter = ',')]
+    pub waypoints: Option<Vec<String>>,
+
+    /// Print a picture of the solution (day 7: beams, day 12: the packing of every region)
+    #[arg(long)]
+    pub render: bool,
+
+    /// Write an SVG picture of the solution to this path (day 9 and 12)
+    #[arg(long)]
+    pub svg: Option<PathBuf>,
+
+    /// Day 1: number of positions on the safe dial
+    #[arg(long, default_value_t = 100)]
+    pub dial_size: u32,
+
+    /// Day 1: position the dial points at before the first rotation
+    #[arg(long, default_value_t = 50)]
+    pub dial_start: u32,
+
+    /// Day 1: print every rotation with its start and end positions and zeros passed
+    #[arg(long)]
+    pub trace: bool,
+
+    /// Day 5: load the fresh ID ranges once, then answer ID and range queries from stdin
+    #[arg(long)]
+    pub serve: bool,
+
+    /// Export the puzzle structure in this format (day 4 and 7: `ppm`, day 8: `newick` or `csv`, day 11: `dot`)
+    #[arg(long, value_enum)]
+    pub export: Option<ExportFormat>,
+
+    /// File to write the export to, or the directory for day 4's frames (defaults to `day<N>...`)
+    #[arg(long)]
+    pub output: Option<PathBuf>,
+
+    /// Day 11: label exported nodes with their topological index and paths from the start
+    #[arg(long)]
+    pub annotate: bool,
+
+    /// Day 11: leave nodes that can't reach the end node out of the export
+    #[arg(long)]
+    pub prune: bool,
+
+    /// Day 7: print how many timelines pass through each cell (part 2)
+    #[arg(long)]
+    pub heatmap: bool,
+
+    /// Day 4: replay the removal rounds in the terminal (part 2)
+    #[arg(long)]
+    pub animate: bool,
+
+    /// Day 4: delay between animation frames in milliseconds
+    #[arg(long, default_value_t = 200)]
+    pub frame_delay_ms: u64,
+
+    /// Day 9: also draw every larger rectangle the part 2 search rejected
+    #[arg(long)]
+    pub show_rejected: bool,
+
+    /// Day 10: list how many times each button is pressed on every machine
+    #[arg(long)]
+    pub explain: bool,
+
+    /// Day 8: list every cluster after connecting this many closest pairs
+    #[arg(long)]
+    pub merges: Option<usize>,
+
+    /// Day 8: list every cluster after connecting all pairs at most this far apart
+    #[arg(long)]
+    pub threshold: Option<f64>,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JoltageSolver {
+    /// Integer programming through lpsolve
+    Lp,
+    /// In-house rational elimination with branch-and-bound over the free presses
+    Exact,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Failure {
+    /// The solver panics
+    Panic,
+    /// The fast and the naive solver give different answers
+    Disagreement,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// Graphviz DOT
+    Dot,
+    /// Plain PPM image
+    Ppm,
+    /// Newick tree
+    Newick,
+    /// Comma-separated rows
+    Csv,
 }
@@ -1,6 +1,9 @@
 use crate::args::Args;
 use crate::utils::input::read_input_lines;
+use crate::utils::rng::Rng;
+use crate::utils::validate::{Problem, numbered, parse};
 use log::info;
+use std::fmt::{Display, Formatter};
 
 pub enum Rotation {
     Left(u32),
@@ -12,12 +15,30 @@ pub struct Safe {
     size: u32,
 }
 
+impl Display for Rotation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Rotation::Left(x) => write!(f, "L{}", x),
+            Rotation::Right(x) => write!(f, "R{}", x),
+        }
+    }
+}
+
 impl Safe {
     pub fn new() -> Safe {
-        Safe {
-            pos: 50u32,
-            size: 100u32,
-        }
+        Safe::with_dial(100, 50)
+    }
+
+    /// A dial numbered `0..size` that points at `pos`.
+    pub fn with_dial(size: u32, pos: u32) -> Safe {
+        assert!(size > 0, "The dial should have at least one number");
+        assert!(
+            pos < size,
+            "Start position {} is not on a dial of size {}",
+            pos,
+            size
+        );
+        Safe { pos, size }
     }
 
     pub fn count_zeroes_during_rotation(&self, rotation: &Rotation) -> u32 {
@@ -69,47 +90,222 @@ impl Default for Safe {
     }
 }
 
+/// Parses `L<n>`/`R<n>` rotations, as well as signed `-<n>`/`+<n>` ones (left and right).
 pub fn read_rotations(input: &[String]) -> Vec<Rotation> {
     input
         .iter()
         .map(|line| {
-            let amount = line[1..].parse::<u32>().unwrap();
+            let line = line.trim();
+            let amount = line
+                .get(1..)
+                .and_then(|amount| amount.parse::<u32>().ok())
+                .unwrap_or_else(|| panic!("Invalid rotation line: {}", line));
             match line {
-                l if l.starts_with("L") => Rotation::Left(amount),
-                l if l.starts_with("R") => Rotation::Right(amount),
+                l if l.starts_with("L") || l.starts_with("-") => Rotation::Left(amount),
+                l if l.starts_with("R") || l.starts_with("+") => Rotation::Right(amount),
                 _ => panic!("Invalid rotation line: {}", line),
             }
         })
         .collect()
 }
 
-fn part1(safe: &mut Safe, rotations: &[Rotation]) {
+#[test]
+fn test_custom_dial() {
+    let mut safe = Safe::with_dial(10, 3);
+    assert_eq!(safe.count_zeroes_during_rotation(&Rotation::Left(3)), 1);
+    assert_eq!(safe.count_zeroes_during_rotation(&Rotation::Right(26)), 2);
+    safe.rotate(&Rotation::Right(26));
+    assert_eq!(safe.get_position(), 9);
+}
+
+#[test]
+fn test_count_zeroes_matches_stepping() {
+    use crate::utils::property::check;
+    check(
+        "zeroes passed equal clicks landing on 0",
+        500,
+        |g| {
+            let size = g.range(1..=150) as u32;
+            let pos = g.range(0..=size as i64 - 1) as u32;
+            (size, pos, g.bool(), g.range(0..=1000) as u32)
+        },
+        |&(size, pos, left, clicks)| {
+            let rotation = if left {
+                Rotation::Left(clicks)
+            } else {
+                Rotation::Right(clicks)
+            };
+            let mut stepped = Safe::with_dial(size, pos);
+            let mut zeroes = 0;
+            for _ in 0..clicks {
+                stepped.rotate(&if left {
+                    Rotation::Left(1)
+                } else {
+                    Rotation::Right(1)
+                });
+                zeroes += stepped.is_at_start() as u32;
+            }
+            let counted = Safe::with_dial(size, pos).count_zeroes_during_rotation(&rotation);
+            if counted == zeroes {
+                Ok(())
+            } else {
+                Err(format!("counted {}, stepping gives {}", counted, zeroes))
+            }
+        },
+    );
+}
+
+#[test]
+fn test_read_rotations() {
+    let lines: Vec<String> = ["L68", "R48", "+37", "-12"]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+    let rotations: Vec<String> = read_rotations(&lines)
+        .iter()
+        .map(|r| r.to_string())
+        .collect();
+    assert_eq!(rotations, vec!["L68", "R48", "R37", "L12"]);
+}
+
+/// Prints one row per rotation with the positions before and after it and how many
+/// times the dial points at 0 on the way.
+fn print_trace(mut safe: Safe, rotations: &[Rotation]) {
+    println!(
+        "{:>6} {:>10} {:>6} {:>6} {:>6}",
+        "#", "rotation", "from", "to", "zeros"
+    );
+    for (i, rotation) in rotations.iter().enumerate() {
+        let from = safe.get_position();
+        let zeros = safe.count_zeroes_during_rotation(rotation);
+        safe.rotate(rotation);
+        println!(
+            "{:>6} {:>10} {:>6} {:>6} {:>6}",
+            i + 1,
+            rotation.to_string(),
+            from,
+            safe.get_position(),
+            zeros
+        );
+    }
+}
+
+fn part1(safe: &mut Safe, rotations: &[Rotation]) -> u32 {
     let mut zero_count = 0;
     for rotation in rotations {
         safe.rotate(rotation);
         info!("Current position: {}", safe.get_position());
         zero_count += if safe.is_at_start() { 1 } else { 0 };
     }
-    println!("The passcode is {}.", zero_count);
+    zero_count
 }
 
-fn part2(safe: &mut Safe, rotations: &[Rotation]) {
+fn part2(safe: &mut Safe, rotations: &[Rotation]) -> u32 {
     let mut zero_count = 0;
     for rotation in rotations {
         zero_count += safe.count_zeroes_during_rotation(rotation);
         safe.rotate(rotation);
     }
-    println!("The 0x434C49434B passcode is {}.", zero_count);
+    zero_count
+}
+
+/// `size` random rotations of up to 999 clicks.
+pub fn generate(rng: &mut Rng, size: usize) -> Vec<String> {
+    (0..size)
+        .map(|_| {
+            let direction = if rng.chance(0.5) { 'L' } else { 'R' };
+            format!("{}{}", direction, rng.range(1..=999))
+        })
+        .collect()
+}
+
+/// Answer for the default dial, without any of the command line options.
+pub fn solve(part: u8, lines: &[String]) -> String {
+    let rotations = read_rotations(lines);
+    let mut safe = Safe::new();
+    match part {
+        1 => part1(&mut safe, &rotations),
+        _ => part2(&mut safe, &rotations),
+    }
+    .to_string()
+}
+
+/// Reference answer that turns the dial one click at a time.
+pub fn solve_naive(part: u8, lines: &[String]) -> String {
+    let mut safe = Safe::new();
+    let mut zero_count = 0;
+    for rotation in read_rotations(lines) {
+        let (clicks, step) = match rotation {
+            Rotation::Left(x) => (x, Rotation::Left(1)),
+            Rotation::Right(x) => (x, Rotation::Right(1)),
+        };
+        for _ in 0..clicks {
+            safe.rotate(&step);
+            if part != 1 && safe.is_at_start() {
+                zero_count += 1;
+            }
+        }
+        if part == 1 && safe.is_at_start() {
+            zero_count += 1;
+        }
+    }
+    zero_count.to_string()
+}
+
+/// Every line is a rotation: `L` or `R` (or `-`/`+`) and a click count.
+pub fn validate(lines: &[String]) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    let rotations = numbered(lines);
+    if rotations.is_empty() {
+        problems.push(Problem::input("There are no rotations"));
+    }
+    for (line, rotation) in rotations {
+        let rotation = rotation.trim();
+        if !rotation.starts_with(['L', 'R', '-', '+']) {
+            problems.push(Problem::at(
+                line,
+                format!("'{}' should start with L, R, - or +", rotation),
+            ));
+        } else {
+            parse::<u32>(line, &rotation[1..], "a click count", &mut problems);
+        }
+    }
+    problems
+}
+
+#[test]
+fn test_validate() {
+    let lines = ["L68", "", "R4x", "U3"].map(String::from);
+    let problems: Vec<String> = validate(&lines).iter().map(|p| p.to_string()).collect();
+    assert_eq!(
+        problems,
+        [
+            "line 3: '4x' is not a click count",
+            "line 4: 'U3' should start with L, R, - or +",
+        ]
+    );
 }
 
 pub fn main(args: &Args) {
     let lines = read_input_lines(1, args.input_tag.as_deref());
     let rotations = read_rotations(&lines);
-    let mut safe = Safe::new();
+    let mut safe = Safe::with_dial(args.dial_size, args.dial_start);
+
+    if args.trace {
+        print_trace(Safe::with_dial(args.dial_size, args.dial_start), &rotations);
+    }
 
     if args.part == 1 {
-        part1(&mut safe, &rotations);
+        println!("The passcode is {}.", part1(&mut safe, &rotations));
     } else {
-        part2(&mut safe, &rotations);
+        println!(
+            "The 0x434C49434B passcode is {}.",
+            part2(&mut safe, &rotations)
+        );
     }
 }
+
+#[test]
+fn test_examples() {
+    crate::days::check_example(1, "test");
+}
@@ -80,13 +80,17 @@
 //
 // Analyze each machine's joltage requirements and button wiring schematics. What is the fewest button presses required to correctly configure the joltage level counters on all of the machines?
 
-use crate::args::Args;
+use crate::args::{Args, JoltageSolver};
+use crate::utils::cancel::checkpoint;
 use crate::utils::input::read_input_lines;
+use crate::utils::rng::Rng;
+use crate::utils::validate::{Problem, numbered, parse};
 use good_lp::{
     Expression, IntoAffineExpression, ProblemVariables, Solution, SolverModel, Variable,
     constraint, default_solver, variable,
 };
 use itertools::Itertools;
+use log::error;
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 enum Light {
@@ -139,7 +143,18 @@ impl Machine {
         }
     }
 
-    fn min_joltage_presses(&self) -> usize {
+    /// Presses per button that bring every joltage counter to its required level with the
+    /// fewest presses in total, or `None` if the levels are unreachable.
+    fn min_joltage_presses(&self, solver: JoltageSolver) -> Option<Vec<usize>> {
+        match solver {
+            JoltageSolver::Lp => self.min_joltage_presses_lp(),
+            JoltageSolver::Exact => {
+                JoltageSystem::new(&self.buttons, &self.joltage_levels)?.min_presses()
+            }
+        }
+    }
+
+    fn min_joltage_presses_lp(&self) -> Option<Vec<usize>> {
         let mut problem = ProblemVariables::new();
         let variables = self
             .buttons
@@ -176,38 +191,433 @@ impl Machine {
         for constr in constraints {
             problem = problem.with(constr);
         }
-        let solution = problem.solve().unwrap();
-        println!("Joltage solution: {:?}", solution.value(variables[0]));
-        variables
+        let solution = problem.solve().ok()?;
+        // The solver works in floating point, so integral values may come back as 2.9999999.
+        Some(
+            variables
+                .iter()
+                .map(|var| solution.value(*var).round() as usize)
+                .collect(),
+        )
+    }
+
+    /// Fewest button presses that turn the lights into the desired combination, as a
+    /// bitmask over `buttons`, or `None` if no combination of buttons reaches it.
+    fn min_lights_presses(&self) -> Option<u64> {
+        let columns: Vec<u64> = self
+            .buttons
             .iter()
-            .map(|var| solution.value(*var) as usize)
-            .sum()
+            .map(|button| button.iter().fold(0u64, |mask, &light| mask | (1 << light)))
+            .collect();
+        let target = self
+            .desired_lights
+            .lights
+            .iter()
+            .enumerate()
+            .filter(|(_, light)| **light == Light::On)
+            .fold(0u64, |mask, (light, _)| mask | (1 << light));
+
+        let solutions = Gf2Solutions::solve(&columns, target, self.desired_lights.lights.len())?;
+        Some(solutions.min_weight())
     }
 
-    fn min_lights_presses(&self) -> usize {
-        let mut presses = 1usize;
-        loop {
-            let any_comb = self
-                .buttons
-                .iter()
-                .combinations(presses)
-                .find(|combination| {
-                    let mut lights = self.initial_lights();
-                    for button in combination {
-                        lights.press_inplace(button);
-                    }
-                    lights == self.desired_lights
-                });
-            if let Some(found) = any_comb {
-                println!("Found combination with {} presses: {:?}", presses, found);
-                return presses;
-            } else {
-                presses += 1;
+    /// Expands a bitmask of pressed buttons into presses per button.
+    fn presses_from_mask(&self, pressed: u64) -> Vec<usize> {
+        (0..self.buttons.len())
+            .map(|i| (pressed >> i) as usize & 1)
+            .collect()
+    }
+
+    /// Lights after pressing every button the given number of times, one press at a time.
+    fn simulate_lights(&self, presses: &[usize]) -> LightCompbination {
+        let mut lights = self.initial_lights();
+        for (button, &count) in self.buttons.iter().zip(presses) {
+            for _ in 0..count {
+                lights.press_inplace(button);
+            }
+        }
+        lights
+    }
+
+    /// Joltage counters after pressing every button the given number of times.
+    fn simulate_joltage(&self, presses: &[usize]) -> Vec<usize> {
+        let mut counters = vec![0usize; self.joltage_levels.len()];
+        for (button, &count) in self.buttons.iter().zip(presses) {
+            for &counter in button {
+                counters[counter] += count;
+            }
+        }
+        counters
+    }
+
+    /// `(1,3) x2, (0,2) x0, ...` for every button in input order.
+    fn explain(&self, presses: &[usize]) -> String {
+        self.buttons
+            .iter()
+            .zip(presses)
+            .map(|(button, count)| format!("({}) x{}", button.iter().join(","), count))
+            .join(", ")
+    }
+
+    fn pressed_buttons(&self, pressed: u64) -> impl Iterator<Item = &Vec<usize>> {
+        self.buttons
+            .iter()
+            .enumerate()
+            .filter(move |(i, _)| pressed & (1 << i) != 0)
+            .map(|(_, button)| button)
+    }
+}
+
+/// Most buttons that don't follow from the others, as every combination of them is tried.
+const MAX_FREE_BUTTONS: usize = 32;
+
+/// All solutions of a linear system over GF(2): `particular` XOR any subset of `nullspace`.
+/// Bit `j` of every vector says whether button `j` is pressed (an odd number of times).
+struct Gf2Solutions {
+    particular: u64,
+    nullspace: Vec<u64>,
+}
+
+impl Gf2Solutions {
+    /// Solves `A x = target` where `columns[j]` is column `j` of `A` as a bitmask over rows.
+    fn solve(columns: &[u64], target: u64, row_count: usize) -> Option<Self> {
+        assert!(columns.len() <= 64, "At most 64 buttons are supported");
+        assert!(row_count <= 64, "At most 64 lights are supported");
+
+        // One (coefficients, rhs) pair per light, coefficients are a bitmask over buttons.
+        let mut rows: Vec<(u64, bool)> = (0..row_count)
+            .map(|row| {
+                let coefficients = columns
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, column)| *column & (1 << row) != 0)
+                    .fold(0u64, |mask, (j, _)| mask | (1 << j));
+                (coefficients, target & (1 << row) != 0)
+            })
+            .collect();
+
+        // Reduced row echelon form; pivots[r] is the pivot column of row r.
+        let mut pivots: Vec<usize> = Vec::new();
+        for column in 0..columns.len() {
+            let rank = pivots.len();
+            let Some(pivot_row) = (rank..rows.len()).find(|&r| rows[r].0 & (1 << column) != 0)
+            else {
+                continue;
+            };
+            rows.swap(rank, pivot_row);
+            let pivot = rows[rank];
+            for (r, row) in rows.iter_mut().enumerate() {
+                if r != rank && row.0 & (1 << column) != 0 {
+                    row.0 ^= pivot.0;
+                    row.1 ^= pivot.1;
+                }
+            }
+            pivots.push(column);
+        }
+
+        if rows[pivots.len()..].iter().any(|&(_, rhs)| rhs) {
+            return None;
+        }
+
+        let particular = pivots
+            .iter()
+            .zip(rows.iter())
+            .filter(|(_, (_, rhs))| *rhs)
+            .fold(0u64, |mask, (&column, _)| mask | (1 << column));
+
+        let nullspace = (0..columns.len())
+            .filter(|column| !pivots.contains(column))
+            .map(|free| {
+                pivots
+                    .iter()
+                    .zip(rows.iter())
+                    .filter(|(_, (coefficients, _))| coefficients & (1 << free) != 0)
+                    .fold(1u64 << free, |mask, (&column, _)| mask | (1 << column))
+            })
+            .collect();
+
+        Some(Gf2Solutions {
+            particular,
+            nullspace,
+        })
+    }
+
+    /// The solution with the fewest set bits, found by walking the nullspace in Gray code order.
+    fn min_weight(&self) -> u64 {
+        assert!(
+            self.nullspace.len() <= MAX_FREE_BUTTONS,
+            "{} free buttons, at most {} are supported",
+            self.nullspace.len(),
+            MAX_FREE_BUTTONS
+        );
+        let mut current = self.particular;
+        let mut best = current;
+        for step in 1u64..(1 << self.nullspace.len()) {
+            if step % 4096 == 0 {
+                checkpoint();
+            }
+            current ^= self.nullspace[step.trailing_zeros() as usize];
+            if current.count_ones() < best.count_ones() {
+                best = current;
+            }
+        }
+        best
+    }
+}
+
+#[test]
+fn test_simulate_presses() {
+    let machine = Machine::from("[.##.] (3) (1,3) (2) (2,3) (0,2) (0,1) {3,5,4,7}");
+    let presses = vec![1, 3, 0, 3, 1, 2];
+    assert_eq!(machine.simulate_joltage(&presses), vec![3, 5, 4, 7]);
+    assert_eq!(
+        machine.explain(&presses),
+        "(3) x1, (1,3) x3, (2) x0, (2,3) x3, (0,2) x1, (0,1) x2"
+    );
+    assert!(
+        machine.simulate_lights(&machine.presses_from_mask(0b110000)) == machine.desired_lights
+    );
+    assert!(machine.simulate_lights(&[0, 0, 0, 0, 2, 0]) != machine.desired_lights);
+}
+
+#[test]
+#[should_panic(expected = "64 free buttons, at most 32 are supported")]
+fn test_min_weight_rejects_large_nullspaces() {
+    // Buttons wired to nothing can all be pressed or not, freely.
+    Gf2Solutions::solve(&[0; 64], 0, 1).unwrap().min_weight();
+}
+
+#[test]
+fn test_min_lights_presses() {
+    let presses: Vec<Option<u32>> = [
+        "[.##.] (3) (1,3) (2) (2,3) (0,2) (0,1) {3,5,4,7}",
+        "[...#.] (0,2,3,4) (2,3) (0,4) (0,1,2) (1,2,3,4) {7,5,12,7,2}",
+        "[.###.#] (0,1,2,3,4) (0,3,4) (0,1,2,4,5) (1,2) {10,11,11,5,10,5}",
+        "[.#.] (0,1) (1,2) {1,1,1}",
+    ]
+    .into_iter()
+    .map(|line| {
+        Machine::from(line)
+            .min_lights_presses()
+            .map(u64::count_ones)
+    })
+    .collect();
+    assert_eq!(presses, vec![Some(2), Some(3), Some(2), None]);
+}
+
+/// The joltage equations `A x = b` (one row per counter, one column per button) brought
+/// into reduced row echelon form with rational elimination. Rows are scaled back to
+/// integers, so a pivot variable is `x_p = (rhs - sum(coefficient_f * x_f)) / denominator`
+/// over the free variables `x_f`.
+struct JoltageSystem {
+    pivot_rows: Vec<PivotRow>,
+    free_columns: Vec<usize>,
+    // Free variables can't exceed the lowest level of any counter their button increases.
+    free_upper_bounds: Vec<i64>,
+    column_count: usize,
+}
+
+struct PivotRow {
+    column: usize,
+    denominator: i64,
+    free_coefficients: Vec<i64>,
+    rhs: i64,
+}
+
+fn gcd(a: i64, b: i64) -> i64 {
+    if b == 0 { a.abs() } else { gcd(b, a % b) }
+}
+
+impl JoltageSystem {
+    fn new(buttons: &[Vec<usize>], levels: &[usize]) -> Option<Self> {
+        let column_count = buttons.len();
+        // Augmented matrix, the last column is the right-hand side.
+        let mut rows: Vec<Vec<i64>> = levels
+            .iter()
+            .enumerate()
+            .map(|(counter, &level)| {
+                let mut row: Vec<i64> = buttons
+                    .iter()
+                    .map(|button| button.contains(&counter) as i64)
+                    .collect();
+                row.push(level as i64);
+                row
+            })
+            .collect();
+
+        let mut pivot_columns: Vec<usize> = Vec::new();
+        for column in 0..column_count {
+            let rank = pivot_columns.len();
+            let Some(pivot_row) = (rank..rows.len()).find(|&r| rows[r][column] != 0) else {
+                continue;
+            };
+            rows.swap(rank, pivot_row);
+            let pivot = rows[rank].clone();
+            for (r, row) in rows.iter_mut().enumerate() {
+                let factor = row[column];
+                if r == rank || factor == 0 {
+                    continue;
+                }
+                for (value, pivot_value) in row.iter_mut().zip(pivot.iter()) {
+                    *value = *value * pivot[column] - pivot_value * factor;
+                }
+                let divisor = row.iter().fold(0, |acc, &v| gcd(acc, v));
+                if divisor > 1 {
+                    row.iter_mut().for_each(|v| *v /= divisor);
+                }
+            }
+            pivot_columns.push(column);
+        }
+
+        if rows[pivot_columns.len()..]
+            .iter()
+            .any(|row| row[column_count] != 0)
+        {
+            return None;
+        }
+
+        let free_columns: Vec<usize> = (0..column_count)
+            .filter(|column| !pivot_columns.contains(column))
+            .collect();
+        let free_upper_bounds = free_columns
+            .iter()
+            .map(|&column| {
+                buttons[column]
+                    .iter()
+                    .map(|&counter| levels[counter] as i64)
+                    .min()
+                    .unwrap_or(0)
+            })
+            .collect();
+        let pivot_rows = pivot_columns
+            .iter()
+            .zip(rows)
+            .map(|(&column, row)| {
+                let sign = row[column].signum();
+                PivotRow {
+                    column,
+                    denominator: row[column] * sign,
+                    free_coefficients: free_columns.iter().map(|&f| row[f] * sign).collect(),
+                    rhs: row[column_count] * sign,
+                }
+            })
+            .collect();
+
+        Some(JoltageSystem {
+            pivot_rows,
+            free_columns,
+            free_upper_bounds,
+            column_count,
+        })
+    }
+
+    /// Branch-and-bound over the free variables within their upper bounds.
+    fn min_presses(&self) -> Option<Vec<usize>> {
+        let mut free_values = vec![0i64; self.free_columns.len()];
+        let mut best: Option<(i64, Vec<usize>)> = None;
+        self.search(0, 0, &mut free_values, &mut best);
+        best.map(|(_, presses)| presses)
+    }
+
+    fn search(
+        &self,
+        free_idx: usize,
+        free_sum: i64,
+        free_values: &mut Vec<i64>,
+        best: &mut Option<(i64, Vec<usize>)>,
+    ) {
+        checkpoint();
+        // Pivot variables are non-negative, so the free presses alone bound the total.
+        if best.as_ref().is_some_and(|(total, _)| free_sum >= *total) {
+            return;
+        }
+        // A pivot variable stays non-negative only if the remaining free variables can
+        // still bring its numerator up to zero.
+        let can_be_non_negative = self.pivot_rows.iter().all(|row| {
+            let assigned: i64 = (0..free_idx)
+                .map(|f| row.free_coefficients[f] * free_values[f])
+                .sum();
+            let best_remaining: i64 = (free_idx..self.free_columns.len())
+                .map(|f| (-row.free_coefficients[f]).max(0) * self.free_upper_bounds[f])
+                .sum();
+            row.rhs - assigned + best_remaining >= 0
+        });
+        if !can_be_non_negative {
+            return;
+        }
+
+        if free_idx < self.free_columns.len() {
+            for value in 0..=self.free_upper_bounds[free_idx] {
+                free_values[free_idx] = value;
+                self.search(free_idx + 1, free_sum + value, free_values, best);
             }
+            free_values[free_idx] = 0;
+            return;
+        }
+
+        let mut presses = vec![0usize; self.column_count];
+        for (&column, &value) in self.free_columns.iter().zip(free_values.iter()) {
+            presses[column] = value as usize;
+        }
+        for row in &self.pivot_rows {
+            let numerator = row.rhs
+                - row
+                    .free_coefficients
+                    .iter()
+                    .zip(free_values.iter())
+                    .map(|(c, v)| c * v)
+                    .sum::<i64>();
+            if numerator < 0 || numerator % row.denominator != 0 {
+                return;
+            }
+            presses[row.column] = (numerator / row.denominator) as usize;
+        }
+        let total = presses.iter().sum::<usize>() as i64;
+        if best
+            .as_ref()
+            .is_none_or(|(best_total, _)| total < *best_total)
+        {
+            *best = Some((total, presses));
         }
     }
 }
 
+#[test]
+fn test_min_joltage_presses_exact() {
+    let presses: Vec<Option<usize>> = [
+        "[.##.] (3) (1,3) (2) (2,3) (0,2) (0,1) {3,5,4,7}",
+        "[...#.] (0,2,3,4) (2,3) (0,4) (0,1,2) (1,2,3,4) {7,5,12,7,2}",
+        "[.###.#] (0,1,2,3,4) (0,3,4) (0,1,2,4,5) (1,2) {10,11,11,5,10,5}",
+        "[..] (0) (0,1) {1,2}",
+    ]
+    .into_iter()
+    .map(|line| {
+        Machine::from(line)
+            .min_joltage_presses(JoltageSolver::Exact)
+            .map(|presses| presses.iter().sum())
+    })
+    .collect();
+    assert_eq!(presses, vec![Some(10), Some(12), Some(11), None]);
+}
+
+#[test]
+fn test_exact_solver_matches_lp() {
+    let lines = read_input_lines(10, None::<&str>);
+    for line in &lines {
+        let machine = Machine::from(line.as_str());
+        let total = |solver| {
+            machine
+                .min_joltage_presses(solver)
+                .map(|presses| presses.iter().sum::<usize>())
+        };
+        assert_eq!(
+            total(JoltageSolver::Exact),
+            total(JoltageSolver::Lp),
+            "{line}"
+        );
+    }
+}
+
 impl From<&str> for Machine {
     fn from(input: &str) -> Self {
         // Example:
@@ -243,6 +653,276 @@ impl From<&str> for Machine {
     }
 }
 
+/// `size` machines with 3-10 lights. The targets come from pressing random buttons, so every
+/// machine has a solution. Small inputs also get small machines, which keeps them within
+/// reach of the exhaustive reference solver.
+pub fn generate(rng: &mut Rng, size: usize) -> Vec<String> {
+    let max_lights = (3 + size / 4).min(10) as i64;
+    (0..size)
+        .map(|_| {
+            let light_count = rng.range(3..=max_lights) as usize;
+            let button_count = (light_count as i64 + rng.range(-1..=3)) as usize;
+            let mut buttons: Vec<Vec<usize>> = (0..button_count)
+                .map(|_| {
+                    let mut lights: Vec<usize> = (0..light_count).collect();
+                    rng.shuffle(&mut lights);
+                    lights.truncate(rng.range(1..=light_count as i64) as usize);
+                    lights.sort_unstable();
+                    lights
+                })
+                .collect();
+            // Every counter needs some button, or its joltage could only ever be zero.
+            for light in 0..light_count {
+                if !buttons.iter().any(|b| b.contains(&light)) {
+                    let button = rng.index(buttons.len());
+                    buttons[button].push(light);
+                    buttons[button].sort_unstable();
+                }
+            }
+            // Puzzle machines never wire two buttons the same way.
+            buttons.sort_unstable();
+            buttons.dedup();
+            rng.shuffle(&mut buttons);
+
+            let mut lights = vec![false; light_count];
+            let mut joltage = vec![0usize; light_count];
+            for button in &buttons {
+                let presses = rng.range(0..=20) as usize;
+                for &light in button {
+                    lights[light] ^= presses % 2 == 1;
+                    joltage[light] += presses;
+                }
+            }
+            format!(
+                "[{}] {} {{{}}}",
+                lights.iter().map(|&on| if on { '#' } else { '.' }).join(""),
+                buttons
+                    .iter()
+                    .map(|b| format!("({})", b.iter().join(",")))
+                    .join(" "),
+                joltage.iter().join(",")
+            )
+        })
+        .collect()
+}
+
+/// Total presses over all machines that can be solved; part 2 uses the in-house exact solver.
+pub fn solve(part: u8, lines: &[String]) -> String {
+    solve_with(part, lines, JoltageSolver::Exact)
+}
+
+/// Same as [`solve`] with part 2 on the given joltage solver.
+pub fn solve_with(part: u8, lines: &[String], solver: JoltageSolver) -> String {
+    let machines = lines.iter().map(|line| Machine::from(line.as_str()));
+    match part {
+        1 => machines
+            .filter_map(|machine| machine.min_lights_presses())
+            .map(|pressed| pressed.count_ones() as usize)
+            .sum::<usize>(),
+        _ => machines
+            .filter_map(|machine| machine.min_joltage_presses(solver))
+            .map(|presses| presses.iter().sum::<usize>())
+            .sum(),
+    }
+    .to_string()
+}
+
+/// Fewest joltage presses by trying every press count for one button after another. The
+/// last button touching a counter has to finish it, and a branch stops once even the
+/// largest remaining counter can't beat the best total found so far.
+fn exhaustive_joltage_presses(
+    buttons: &[Vec<usize>],
+    button: usize,
+    remaining: &mut Vec<usize>,
+    pressed: usize,
+    best: &mut Option<usize>,
+) {
+    checkpoint();
+    let lower_bound = pressed + remaining.iter().max().copied().unwrap_or(0);
+    if best.is_some_and(|best| lower_bound >= best) {
+        return;
+    }
+    if button == buttons.len() {
+        if remaining.iter().all(|&c| c == 0) {
+            *best = Some(pressed);
+        }
+        return;
+    }
+
+    let max_presses = buttons[button]
+        .iter()
+        .map(|&counter| remaining[counter])
+        .min()
+        .unwrap_or(0);
+    let finishing: Vec<usize> = buttons[button]
+        .iter()
+        .copied()
+        .filter(|&counter| buttons[button + 1..].iter().all(|b| !b.contains(&counter)))
+        .collect();
+    let choices = match finishing.first() {
+        Some(&counter) => {
+            let forced = remaining[counter];
+            if forced > max_presses || finishing.iter().any(|&c| remaining[c] != forced) {
+                return;
+            }
+            forced..=forced
+        }
+        None => 0..=max_presses,
+    };
+
+    for presses in choices.rev() {
+        for &counter in &buttons[button] {
+            remaining[counter] -= presses;
+        }
+        exhaustive_joltage_presses(buttons, button + 1, remaining, pressed + presses, best);
+        for &counter in &buttons[button] {
+            remaining[counter] += presses;
+        }
+    }
+}
+
+/// Reference answer by exhaustive search: every subset of buttons for part 1, every press
+/// count per button for part 2. Only practical for small machines.
+pub fn solve_naive(part: u8, lines: &[String]) -> String {
+    let machines = lines.iter().map(|line| Machine::from(line.as_str()));
+    match part {
+        1 => machines
+            .filter_map(|machine| {
+                (0u64..1 << machine.buttons.len())
+                    .filter(|&pressed| {
+                        machine.simulate_lights(&machine.presses_from_mask(pressed))
+                            == machine.desired_lights
+                    })
+                    .map(|pressed| pressed.count_ones() as usize)
+                    .min()
+            })
+            .sum::<usize>(),
+        _ => machines
+            .filter_map(|machine| {
+                let mut best = None;
+                exhaustive_joltage_presses(
+                    &machine.buttons,
+                    0,
+                    &mut machine.joltage_levels.clone(),
+                    0,
+                    &mut best,
+                );
+                best
+            })
+            .sum(),
+    }
+    .to_string()
+}
+
+/// Every machine is `[lights] (buttons)... {joltages}`: buttons only wire existing lights,
+/// and there is one joltage per light.
+pub fn validate(lines: &[String]) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    let machines = numbered(lines);
+    if machines.is_empty() {
+        problems.push(Problem::input("There are no machines"));
+    }
+    for (line, machine) in machines {
+        let parts: Vec<&str> = machine.split_whitespace().collect();
+        let Some(lights) = parts
+            .first()
+            .and_then(|p| p.strip_prefix('['))
+            .and_then(|p| p.strip_suffix(']'))
+        else {
+            problems.push(Problem::at(line, "Machine should start with [lights]"));
+            continue;
+        };
+        if let Some(c) = lights.chars().find(|&c| c != '.' && c != '#') {
+            problems.push(Problem::at(
+                line,
+                format!("Invalid light character '{}'", c),
+            ));
+        }
+        let light_count = lights.chars().count();
+        if light_count > 64 {
+            problems.push(Problem::at(
+                line,
+                format!("{} lights, at most 64 are supported", light_count),
+            ));
+        }
+
+        let Some(joltages) = parts
+            .last()
+            .and_then(|p| p.strip_prefix('{'))
+            .and_then(|p| p.strip_suffix('}'))
+            .filter(|_| parts.len() > 1)
+        else {
+            problems.push(Problem::at(line, "Machine should end with {joltages}"));
+            continue;
+        };
+        let joltage_count = joltages.split(',').count();
+        if joltage_count != light_count {
+            problems.push(Problem::at(
+                line,
+                format!("{} joltages for {} lights", joltage_count, light_count),
+            ));
+        }
+        for joltage in joltages.split(',') {
+            parse::<usize>(line, joltage, "a joltage", &mut problems);
+        }
+
+        let buttons = &parts[1..parts.len() - 1];
+        if buttons.is_empty() {
+            problems.push(Problem::at(line, "Machine has no buttons"));
+        }
+        if buttons.len() > 64 {
+            problems.push(Problem::at(
+                line,
+                format!("{} buttons, at most 64 are supported", buttons.len()),
+            ));
+        }
+        for button in buttons {
+            let Some(wiring) = button.strip_prefix('(').and_then(|b| b.strip_suffix(')')) else {
+                problems.push(Problem::at(line, format!("'{}' is not a (button)", button)));
+                continue;
+            };
+            for light in wiring.split(',') {
+                if let Some(light) = parse::<usize>(line, light, "a light index", &mut problems)
+                    && light >= light_count
+                {
+                    problems.push(Problem::at(
+                        line,
+                        format!("Button {} wires light {} of {}", button, light, light_count),
+                    ));
+                }
+            }
+        }
+    }
+    problems
+}
+
+#[test]
+fn test_validate() {
+    let mut lines = [
+        "[.##.] (3) (1,3) (2) (2,3) (0,2) (0,1) {3,5,4,7}",
+        "[..x] (0,3) {1,2}",
+        "(0) {1}",
+    ]
+    .map(String::from)
+    .to_vec();
+    lines.push(format!(
+        "[{}] (0) {{{}}}",
+        ".".repeat(65),
+        vec!["0"; 65].join(",")
+    ));
+    let problems = validate(&lines).iter().map(|p| p.to_string()).collect_vec();
+    assert_eq!(
+        problems,
+        [
+            "line 2: Invalid light character 'x'",
+            "line 2: 2 joltages for 3 lights",
+            "line 2: Button (0,3) wires light 3 of 3",
+            "line 3: Machine should start with [lights]",
+            "line 4: 65 lights, at most 64 are supported",
+        ]
+    );
+}
+
 pub fn main(args: &Args) {
     let input_lines = read_input_lines(args.day as u32, args.input_tag.as_deref());
     let machines: Vec<Machine> = input_lines
@@ -250,12 +930,48 @@ pub fn main(args: &Args) {
         .map(|line| Machine::from(line.as_str()))
         .collect();
 
+    // Every solver answer is replayed by simulation before it is counted.
+    let mut failed_replays = 0usize;
+    let mut report_replay = |machine_idx: usize, reached: String, expected: String| {
+        failed_replays += 1;
+        let message = format!(
+            "Machine {}: REPLAY FAILED, the presses reach {} instead of {}",
+            machine_idx + 1,
+            reached,
+            expected
+        );
+        error!("{}", message);
+        println!("{}", message);
+    };
+
     match args.part {
         1 => {
             let mut total_presses = 0usize;
             for (i, machine) in machines.iter().enumerate() {
-                let min_presses = machine.min_lights_presses();
+                let Some(pressed) = machine.min_lights_presses() else {
+                    println!("Machine {}: Desired lights are unreachable", i + 1);
+                    continue;
+                };
+                let min_presses = pressed.count_ones() as usize;
+                println!(
+                    "Found combination with {} presses: {:?}",
+                    min_presses,
+                    machine.pressed_buttons(pressed).collect::<Vec<_>>()
+                );
                 println!("Machine {}: Minimum presses = {}", i + 1, min_presses);
+
+                let presses = machine.presses_from_mask(pressed);
+                if args.explain {
+                    println!("Machine {}: {}", i + 1, machine.explain(&presses));
+                }
+                let lights = machine.simulate_lights(&presses);
+                if lights != machine.desired_lights {
+                    report_replay(
+                        i,
+                        format!("{:?}", lights.lights),
+                        format!("{:?}", machine.desired_lights.lights),
+                    );
+                }
                 total_presses += min_presses;
             }
 
@@ -264,12 +980,28 @@ pub fn main(args: &Args) {
         2 => {
             let mut total_presses = 0usize;
             for (i, machine) in machines.iter().enumerate() {
-                let min_presses = machine.min_joltage_presses();
+                let Some(presses) = machine.min_joltage_presses(args.joltage_solver) else {
+                    println!("Machine {}: Joltage levels are unreachable", i + 1);
+                    continue;
+                };
+                let min_presses: usize = presses.iter().sum();
                 println!(
                     "Machine {}: Minimum joltage presses = {}",
                     i + 1,
                     min_presses
                 );
+
+                if args.explain {
+                    println!("Machine {}: {}", i + 1, machine.explain(&presses));
+                }
+                let counters = machine.simulate_joltage(&presses);
+                if counters != machine.joltage_levels {
+                    report_replay(
+                        i,
+                        format!("{:?}", counters),
+                        format!("{:?}", machine.joltage_levels),
+                    );
+                }
                 total_presses += min_presses;
             }
 
@@ -279,4 +1011,17 @@ pub fn main(args: &Args) {
             println!("Part {} is not implemented yet.", args.part);
         }
     }
+
+    if failed_replays > 0 {
+        error!("{} machines failed the replay check", failed_replays);
+        println!(
+            "WARNING: {} machines failed the replay check, the total is not trustworthy",
+            failed_replays
+        );
+    }
+}
+
+#[test]
+fn test_examples() {
+    crate::days::check_example(10, "test");
 }
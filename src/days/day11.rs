@@ -76,9 +76,15 @@
 //
 // Your puzzle answer was 331468292364745.
 
-use crate::args::Args;
+use crate::args::{Args, ExportFormat};
+use crate::utils::cancel::checkpoint;
 use crate::utils::input::read_input_lines;
+use crate::utils::rng::Rng;
+use crate::utils::validate::{Problem, numbered};
+use itertools::Itertools;
+use log::info;
 use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
 use topo_sort::{SortResults, TopoSort};
 
 struct Graph {
@@ -87,26 +93,6 @@ struct Graph {
     node_names: Vec<String>,
 }
 
-impl Graph {
-    fn get_name_by_idx(&self, idx: u32) -> &str {
-        &self.node_names[idx as usize]
-    }
-}
-
-struct DfsState {
-    current_node: u32,
-    visited_list_idx: Option<u32>,
-}
-
-impl DfsState {
-    fn with_visited_idx(&self, idx: u32) -> Self {
-        DfsState {
-            current_node: self.current_node,
-            visited_list_idx: Some(idx),
-        }
-    }
-}
-
 fn edges_to_topsorted<'a>(edges: &'a Vec<(&'a str, Vec<&'a str>)>) -> Vec<&'a str> {
     let mut topo_sort = TopoSort::with_capacity(edges.len());
     for (node, connections) in edges.iter() {
@@ -169,87 +155,496 @@ impl Graph {
         }
     }
 
-    fn scan_paths(&self, start_node: &str, end_node: &str) -> usize {
-        let start_node_idx = *self.name_to_idx.get(start_node).unwrap();
-        let end_node_idx = *self.name_to_idx.get(end_node).unwrap();
-        if start_node_idx > end_node_idx {
-            // No paths possible in a DAG
-            return 0;
+    fn get_idx_by_name(&self, name: &str) -> u32 {
+        *self
+            .name_to_idx
+            .get(name)
+            .unwrap_or_else(|| panic!("Node '{}' is not in the graph", name))
+    }
+
+    /// Number of paths from `start_node` to `end_node` that visit every waypoint, in any
+    /// order. Runs the path count DP over (node, subset of visited waypoints) pairs.
+    fn count_paths_through(&self, start_node: &str, end_node: &str, waypoints: &[String]) -> u128 {
+        // A waypoint named twice is still visited once, and would otherwise need two bits.
+        let waypoints: Vec<&String> = waypoints.iter().unique().collect();
+        assert!(
+            waypoints.len() < 16,
+            "Too many waypoints: {}",
+            waypoints.len()
+        );
+        let waypoint_bits: HashMap<u32, usize> = waypoints
+            .iter()
+            .enumerate()
+            .map(|(bit, name)| (self.get_idx_by_name(name), 1usize << bit))
+            .collect();
+        let bit_of = |node: u32| waypoint_bits.get(&node).copied().unwrap_or(0);
+        let subsets = 1usize << waypoints.len();
+
+        let start_node_idx = self.get_idx_by_name(start_node);
+        let end_node_idx = self.get_idx_by_name(end_node);
+        // paths[node * subsets + visited]
+        let mut paths = vec![0u128; self.node_names.len() * subsets];
+        paths[start_node_idx as usize * subsets + bit_of(start_node_idx)] = 1;
+        for node in start_node_idx as usize..self.adjacency_list.len() {
+            checkpoint();
+            for visited in 0..subsets {
+                let count = paths[node * subsets + visited];
+                if count == 0 {
+                    continue;
+                }
+                for &next in &self.adjacency_list[node] {
+                    let slot = &mut paths[next as usize * subsets + (visited | bit_of(next))];
+                    *slot = self.add_paths(*slot, count, next);
+                }
+            }
         }
-        let mut stack: Vec<DfsState> = vec![DfsState {
-            current_node: start_node_idx,
-            visited_list_idx: None,
-        }];
-        let mut visited = HashSet::from([start_node_idx]);
-        let mut total_paths = 0usize;
-        while let Some(state) = stack.pop() {
-            visited.remove(&state.current_node);
-            // if state.visited_list_idx.is_none() {
-            //     // Just entered this node
-            // }
-            // println!("Entered node {}, visited: {:?}", self.node_names[state.current_node as usize], visited);
-
-            if state.current_node == end_node_idx {
-                // Found a path to the end
-                total_paths += 1;
-                continue;
+        paths[end_node_idx as usize * subsets + subsets - 1]
+    }
+}
+
+/// What to draw in the Graphviz export besides the plain device network.
+struct DotOptions<'a> {
+    start_node: &'a str,
+    end_node: &'a str,
+    waypoints: &'a [String],
+    // Label nodes with their topological index and number of paths from the start.
+    annotate: bool,
+    // Leave out nodes that can't reach the end node.
+    prune: bool,
+}
+
+impl Graph {
+    /// Number of paths from `start_node` to every node, in a single pass over the
+    /// topological order (which is also the node index order).
+    fn count_paths_from(&self, start_node: &str) -> Vec<u128> {
+        let start_node_idx = self.get_idx_by_name(start_node) as usize;
+        let mut paths = vec![0u128; self.node_names.len()];
+        paths[start_node_idx] = 1;
+        for node in start_node_idx..self.adjacency_list.len() {
+            for &next in &self.adjacency_list[node] {
+                paths[next as usize] = self.add_paths(paths[next as usize], paths[node], next);
             }
+        }
+        paths
+    }
 
-            let next_to_visit_start = state.visited_list_idx.map(|idx| idx + 1).unwrap_or(0u32);
+    fn add_paths(&self, paths: u128, more: u128, node: u32) -> u128 {
+        paths.checked_add(more).unwrap_or_else(|| {
+            panic!(
+                "More than {} paths reach node '{}'",
+                u128::MAX,
+                self.node_names[node as usize]
+            )
+        })
+    }
 
-            let current_adjacency = &self.adjacency_list[state.current_node as usize];
-            let next_to_visit = current_adjacency
-                [(next_to_visit_start as usize)..current_adjacency.len()]
+    /// Whether each node has a path to `end_node`, walking the topological order backwards.
+    fn reaches(&self, end_node: &str) -> Vec<bool> {
+        let end_node_idx = self.get_idx_by_name(end_node) as usize;
+        let mut reaches = vec![false; self.node_names.len()];
+        reaches[end_node_idx] = true;
+        for node in (0..end_node_idx).rev() {
+            reaches[node] = self.adjacency_list[node]
                 .iter()
-                .enumerate()
-                .find(|(_idx, node_id)| **node_id <= end_node_idx && !visited.contains(*node_id));
-
-            if let Some((idx, node_id)) = next_to_visit {
-                // Exit this node
-                stack.push(state.with_visited_idx(idx as u32 + next_to_visit_start));
-                visited.insert(state.current_node);
-                visited.insert(*node_id);
-                stack.push(DfsState {
-                    current_node: *node_id,
-                    visited_list_idx: None,
-                })
+                .any(|&next| reaches[next as usize]);
+        }
+        reaches
+    }
+
+    /// The network in Graphviz format. Start, end and waypoint nodes are filled, and
+    /// edges that lie on some path from the start to the end are drawn bold.
+    fn to_dot(&self, options: &DotOptions) -> String {
+        let paths_from_start = self.count_paths_from(options.start_node);
+        let reaches_end = self.reaches(options.end_node);
+        let on_path = |node: usize| paths_from_start[node] > 0 && reaches_end[node];
+        let is_kept = |node: usize| !options.prune || reaches_end[node];
+
+        let mut dot = String::from("digraph reactor {\n    rankdir=LR;\n    node [shape=box];\n");
+        for (node, name) in self.node_names.iter().enumerate() {
+            if !is_kept(node) {
+                continue;
+            }
+            let mut attributes = Vec::new();
+            if options.annotate {
+                attributes.push(format!(
+                    "label=\"{}\\n#{}\\n{} paths\"",
+                    name, node, paths_from_start[node]
+                ));
+            }
+            let fill = if name == options.start_node {
+                Some("palegreen")
+            } else if name == options.end_node {
+                Some("lightcoral")
+            } else if options.waypoints.contains(name) {
+                Some("gold")
+            } else {
+                None
+            };
+            if let Some(fill) = fill {
+                attributes.push(format!("style=filled, fillcolor={}", fill));
+            }
+            if attributes.is_empty() {
+                dot.push_str(&format!("    \"{}\";\n", name));
+            } else {
+                dot.push_str(&format!("    \"{}\" [{}];\n", name, attributes.join(", ")));
             }
         }
-        total_paths
+        for (node, adjacency) in self.adjacency_list.iter().enumerate() {
+            for &next in adjacency.iter().filter(|&&next| is_kept(next as usize)) {
+                let style = if on_path(node) && on_path(next as usize) {
+                    " [color=red, penwidth=2]"
+                } else {
+                    ""
+                };
+                dot.push_str(&format!(
+                    "    \"{}\" -> \"{}\"{};\n",
+                    self.node_names[node], self.node_names[next as usize], style
+                ));
+            }
+        }
+        dot.push_str("}\n");
+        dot
     }
 }
 
-pub fn main(args: &Args) {
-    let lines = read_input_lines(args.day as u32, args.input_tag.as_deref());
-    let graph = Graph::from_edges(lines);
-    match args.part {
-        1 => {
-            let total_paths = graph.scan_paths("you", "out");
-            println!("Total paths from 'you' to 'out': {}", total_paths);
+#[test]
+fn test_to_dot() {
+    let lines = ["you: aaa bbb", "aaa: out", "bbb: ccc", "ccc:"];
+    let graph = Graph::from_edges(lines.iter().map(|l| l.to_string()).collect());
+    let dot = graph.to_dot(&DotOptions {
+        start_node: "you",
+        end_node: "out",
+        waypoints: &["aaa".to_string()],
+        annotate: true,
+        prune: true,
+    });
+    assert!(dot.starts_with("digraph reactor {"));
+    assert!(
+        dot.contains("\"you\" [label=\"you\\n#0\\n1 paths\", style=filled, fillcolor=palegreen];")
+    );
+    assert!(dot.contains("\"aaa\" [label=\"aaa\\n#"));
+    assert!(dot.contains("\"you\" -> \"aaa\" [color=red, penwidth=2];"));
+    assert!(!dot.contains("ccc"));
+    assert!(!dot.contains("\"you\" -> \"bbb\""));
+}
+
+#[test]
+fn test_count_paths() {
+    let lines = [
+        "aaa: you hhh",
+        "you: bbb ccc",
+        "bbb: ddd eee",
+        "ccc: ddd eee fff",
+        "ddd: ggg",
+        "eee: out",
+        "fff: out",
+        "ggg: out",
+        "hhh: ccc fff iii",
+        "iii: out",
+    ];
+    let graph = Graph::from_edges(lines.iter().map(|l| l.to_string()).collect());
+    assert_eq!(graph.count_paths_through("you", "out", &[]), 5);
+    assert_eq!(graph.count_paths_through("out", "you", &[]), 0);
+}
+
+#[test]
+fn test_count_paths_through_waypoints() {
+    let lines = [
+        "svr: aaa bbb",
+        "aaa: fft",
+        "fft: ccc",
+        "bbb: tty",
+        "tty: ccc",
+        "ccc: ddd eee",
+        "ddd: hub",
+        "hub: fff",
+        "eee: dac",
+        "dac: fff",
+        "fff: ggg hhh",
+        "ggg: out",
+        "hhh: out",
+    ];
+    let graph = Graph::from_edges(lines.iter().map(|l| l.to_string()).collect());
+    let waypoints = |names: &[&str]| names.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+    assert_eq!(graph.count_paths_through("svr", "out", &[]), 8);
+    assert_eq!(
+        graph.count_paths_through("svr", "out", &waypoints(&["dac", "fft"])),
+        2
+    );
+    assert_eq!(
+        graph.count_paths_through("svr", "out", &waypoints(&["fft", "dac"])),
+        2
+    );
+    assert_eq!(
+        graph.count_paths_through("svr", "out", &waypoints(&["hub", "dac"])),
+        0
+    );
+    assert_eq!(
+        graph.count_paths_through("svr", "out", &waypoints(&["svr", "ccc", "out"])),
+        8
+    );
+    assert_eq!(
+        graph.count_paths_through("svr", "out", &waypoints(&["fft", "dac", "fft"])),
+        2
+    );
+}
+
+#[test]
+#[should_panic(expected = "paths reach node")]
+fn test_count_paths_overflow() {
+    // 130 diamonds in a row, so 2^130 paths from the first node to `out`.
+    let lines: Vec<String> = (0..130)
+        .flat_map(|i| {
+            let next = if i == 129 {
+                "out".to_string()
+            } else {
+                format!("n{}", i + 1)
+            };
+            [
+                format!("n{}: a{} b{}", i, i, i),
+                format!("a{}: {}", i, next),
+                format!("b{}: {}", i, next),
+            ]
+        })
+        .collect();
+    Graph::from_edges(lines).count_paths_through("n0", "out", &[]);
+}
+
+/// A DAG of about `size` devices in topological order `svr`, …, `you`, …, `dac`, …, `fft`,
+/// …, `out`. Every device links to the next one, so all of them reach `out`; extra forward
+/// links are rare enough that the path counts stay well inside `u128`.
+pub fn generate(rng: &mut Rng, size: usize) -> Vec<String> {
+    const SPECIAL: [&str; 5] = ["svr", "you", "dac", "fft", "out"];
+    let count = size.clamp(SPECIAL.len(), 26 * 26 * 26);
+    let mut names: Vec<String> = (0..26 * 26 * 26)
+        .map(|i| {
+            let letter = |n: usize| (b'a' + n as u8) as char;
+            format!(
+                "{}{}{}",
+                letter(i / 676),
+                letter(i / 26 % 26),
+                letter(i % 26)
+            )
+        })
+        .filter(|name| !SPECIAL.contains(&name.as_str()))
+        .collect();
+    rng.shuffle(&mut names);
+    names.truncate(count - SPECIAL.len());
+    names.insert(0, "svr".to_string());
+    names.insert(count / 4, "you".to_string());
+    names.insert(count / 2, "dac".to_string());
+    names.insert(3 * count / 4, "fft".to_string());
+    names.push("out".to_string());
+
+    let extra_link = (40.0 / count as f64).min(0.3);
+    (0..count - 1)
+        .map(|i| {
+            let mut targets = vec![i + 1];
+            while rng.chance(extra_link) {
+                let target = (i + 2 + rng.index(20)).min(count - 1);
+                if !targets.contains(&target) {
+                    targets.push(target);
+                }
+            }
+            rng.shuffle(&mut targets);
+            format!(
+                "{}: {}",
+                names[i],
+                targets.iter().map(|&t| names[t].as_str()).join(" ")
+            )
+        })
+        .collect()
+}
+
+pub fn solve(part: u8, lines: &[String]) -> String {
+    let graph = Graph::from_edges(lines.to_vec());
+    match part {
+        1 => graph.count_paths_through("you", "out", &[]),
+        _ => graph.count_paths_through("svr", "out", &["dac".to_string(), "fft".to_string()]),
+    }
+    .to_string()
+}
+
+/// Reference answer that walks every path one at a time with a DFS over the raw edges.
+pub fn solve_naive(part: u8, lines: &[String]) -> String {
+    let edges: HashMap<&str, Vec<&str>> = lines
+        .iter()
+        .filter_map(|line| line.split_once(':'))
+        .map(|(from, to)| (from.trim(), to.split_whitespace().collect()))
+        .collect();
+
+    fn walk(edges: &HashMap<&str, Vec<&str>>, node: &str, required: &[&str], seen: usize) -> u128 {
+        checkpoint();
+        let seen = seen + required.iter().filter(|&&r| r == node).count();
+        if node == "out" {
+            return (seen == required.len()) as u128;
         }
-        2 => {
-            let mut nodes_in_path = [
-                *graph.name_to_idx.get("svr").unwrap(),
-                *graph.name_to_idx.get("dac").unwrap(),
-                *graph.name_to_idx.get("fft").unwrap(),
-                *graph.name_to_idx.get("out").unwrap(),
-            ];
-            nodes_in_path.sort();
-            let mut path_counts: Vec<usize> = Vec::new();
-            for i in 1..nodes_in_path.len() {
-                let from_node = graph.get_name_by_idx(nodes_in_path[i - 1]);
-                let to_node = graph.get_name_by_idx(nodes_in_path[i]);
-                let path_count = graph.scan_paths(from_node, to_node);
-                path_counts.push(path_count);
+        edges
+            .get(node)
+            .into_iter()
+            .flatten()
+            .map(|next| walk(edges, next, required, seen))
+            .sum()
+    }
+
+    match part {
+        1 => walk(&edges, "you", &[], 0),
+        _ => walk(&edges, "svr", &["dac", "fft"], 0),
+    }
+    .to_string()
+}
+
+/// Every line is `node: outputs...`, each node listed once. The graph has to be acyclic, with
+/// `out` reachable from whichever of the start nodes `you` (part 1) and `svr` (part 2) it has.
+pub fn validate(lines: &[String]) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    let mut edges: HashMap<&str, (usize, Vec<&str>)> = HashMap::new();
+    for (line, row) in numbered(lines) {
+        let Some((node, outputs)) = row.split_once(':') else {
+            problems.push(Problem::at(line, "Line should be 'node: outputs...'"));
+            continue;
+        };
+        let node = node.trim();
+        let outputs: Vec<&str> = outputs.split_whitespace().collect();
+        if node == "out" && !outputs.is_empty() {
+            problems.push(Problem::at(line, "'out' should have no outputs"));
+        } else if let Some(&(first_line, _)) = edges.get(node) {
+            problems.push(Problem::at(
+                line,
+                format!("Node '{}' was already listed on line {}", node, first_line),
+            ));
+        } else {
+            edges.insert(node, (line, outputs));
+        }
+    }
+    let outputs = |node: &str| edges.get(node).map_or(&[][..], |(_, outputs)| outputs);
+
+    // Depth-first search; reaching a node that is still being visited closes a cycle.
+    let mut finished: HashMap<&str, bool> = HashMap::new();
+    let roots: Vec<&str> = edges
+        .iter()
+        .sorted_by_key(|(_, (line, _))| *line)
+        .map(|(&node, _)| node)
+        .collect();
+    for root in roots {
+        if finished.contains_key(root) {
+            continue;
+        }
+        let mut stack = vec![(root, 0)];
+        while let Some((node, next)) = stack.pop() {
+            if next == 0 {
+                finished.insert(node, false);
+            }
+            let Some(&output) = outputs(node).get(next) else {
+                finished.insert(node, true);
+                continue;
+            };
+            stack.push((node, next + 1));
+            match finished.get(output) {
+                Some(true) => {}
+                Some(false) => problems.push(Problem::at(
+                    edges[node].0,
+                    format!("Edge {} -> {} closes a cycle", node, output),
+                )),
+                None => stack.push((output, 0)),
             }
-            let total_paths: usize = path_counts.iter().product();
-            println!(
-                "Total paths from 'svr' to 'out' visiting 'dac' and 'fft': {}",
-                total_paths
-            );
         }
+    }
+
+    let starts: Vec<&str> = ["you", "svr"]
+        .into_iter()
+        .filter(|start| edges.contains_key(start))
+        .collect();
+    if starts.is_empty() {
+        problems.push(Problem::input("Neither 'you' nor 'svr' is in the graph"));
+    }
+    for start in starts {
+        let mut seen = HashSet::from([start]);
+        let mut stack = vec![start];
+        while let Some(node) = stack.pop() {
+            stack.extend(outputs(node).iter().filter(|&&next| seen.insert(next)));
+        }
+        if !seen.contains(&"out") {
+            problems.push(Problem::at(
+                edges[start].0,
+                format!("'out' can't be reached from '{}'", start),
+            ));
+        }
+    }
+    problems
+}
+
+#[test]
+fn test_validate() {
+    assert!(validate(&read_input_lines(11, Some("test"))).is_empty());
+    assert!(validate(&read_input_lines(11, Some("test2"))).is_empty());
+    let lines = ["you: aaa bbb", "aaa: bbb", "bbb: aaa", "aaa: out", "ccc"].map(String::from);
+    let problems = validate(&lines).iter().map(|p| p.to_string()).collect_vec();
+    assert_eq!(
+        problems,
+        [
+            "line 4: Node 'aaa' was already listed on line 2",
+            "line 5: Line should be 'node: outputs...'",
+            "line 3: Edge bbb -> aaa closes a cycle",
+            "line 1: 'out' can't be reached from 'you'",
+        ]
+    );
+}
+
+pub fn main(args: &Args) {
+    let lines = read_input_lines(args.day as u32, args.input_tag.as_deref());
+    let graph = Graph::from_edges(lines);
+    let (default_start, default_waypoints): (&str, &[&str]) = match args.part {
+        1 => ("you", &[]),
+        2 => ("svr", &["dac", "fft"]),
         _ => {
             println!("Part {} is not yet implemented", args.part);
+            return;
         }
+    };
+    let start_node = args.start.as_deref().unwrap_or(default_start);
+    let end_node = args.end.as_deref().unwrap_or("out");
+    let waypoints: Vec<String> = args
+        .waypoints
+        .clone()
+        .unwrap_or_else(|| default_waypoints.iter().map(|w| w.to_string()).collect());
+
+    if let Some(ExportFormat::Dot) = args.export {
+        let path = args
+            .output
+            .clone()
+            .unwrap_or_else(|| PathBuf::from("day11.dot"));
+        let dot = graph.to_dot(&DotOptions {
+            start_node,
+            end_node,
+            waypoints: &waypoints,
+            annotate: args.annotate,
+            prune: args.prune,
+        });
+        std::fs::write(&path, dot)
+            .unwrap_or_else(|e| panic!("Unable to write {}: {e}", path.display()));
+        info!("Wrote the device network to {}", path.display());
+    }
+
+    let total_paths = graph.count_paths_through(start_node, end_node, &waypoints);
+    if waypoints.is_empty() {
+        println!(
+            "Total paths from '{}' to '{}': {}",
+            start_node, end_node, total_paths
+        );
+    } else {
+        println!(
+            "Total paths from '{}' to '{}' visiting {}: {}",
+            start_node,
+            end_node,
+            waypoints.iter().map(|w| format!("'{}'", w)).join(" and "),
+            total_paths
+        );
     }
 }
+
+#[test]
+fn test_examples() {
+    crate::days::check_example(11, "test");
+    crate::days::check_example(11, "test2");
+}
@@ -1,5 +1,212 @@
 use crate::args::Args;
+use crate::utils::cancel::checkpoint;
 use crate::utils::input::read_input_lines;
+use crate::utils::point::Point;
+use crate::utils::rng::Rng;
+use crate::utils::svg::Svg;
+use crate::utils::validate::{Problem, numbered, parse};
+use itertools::Itertools;
+use log::info;
+use std::collections::HashSet;
+use std::fmt::{Display, Formatter};
+
+/// A present as the set of cells it covers, normalised so that its bounding box starts
+/// at (0, 0) and cells are sorted in row-major order.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Shape {
+    cells: Vec<Point>,
+}
+
+impl Shape {
+    fn from_cells(cells: impl Iterator<Item = (i64, i64)>) -> Self {
+        let cells: Vec<(i64, i64)> = cells.collect();
+        let min_x = cells.iter().map(|c| c.0).min().unwrap_or(0);
+        let min_y = cells.iter().map(|c| c.1).min().unwrap_or(0);
+        Shape {
+            cells: cells
+                .into_iter()
+                .map(|(x, y)| Point {
+                    x: (x - min_x) as usize,
+                    y: (y - min_y) as usize,
+                })
+                .sorted_by_key(|p| (p.y, p.x))
+                .collect(),
+        }
+    }
+
+    fn from_rows(rows: &[&str]) -> Self {
+        Shape::from_cells(rows.iter().enumerate().flat_map(|(y, row)| {
+            row.chars()
+                .enumerate()
+                .filter(|&(_, c)| c == '#')
+                .map(move |(x, _)| (x as i64, y as i64))
+        }))
+    }
+
+    fn width(&self) -> usize {
+        self.cells.iter().map(|p| p.x + 1).max().unwrap_or(0)
+    }
+
+    fn height(&self) -> usize {
+        self.cells.iter().map(|p| p.y + 1).max().unwrap_or(0)
+    }
+
+    /// All distinct rotations and reflections of the shape.
+    fn orientations(&self) -> Vec<Shape> {
+        let mut orientations: Vec<Shape> = Vec::with_capacity(8);
+        for reflect in [false, true] {
+            for rotations in 0..4 {
+                let shape = Shape::from_cells(self.cells.iter().map(|p| {
+                    let (mut x, mut y) = (p.x as i64, p.y as i64);
+                    if reflect {
+                        x = -x;
+                    }
+                    for _ in 0..rotations {
+                        (x, y) = (y, -x);
+                    }
+                    (x, y)
+                }));
+                if !orientations.contains(&shape) {
+                    orientations.push(shape);
+                }
+            }
+        }
+        orientations
+    }
+}
+
+#[test]
+fn test_orientations() {
+    let square = Shape::from_rows(&["##", "##"]);
+    assert_eq!(square.orientations().len(), 1);
+    let line = Shape::from_rows(&["###"]);
+    assert_eq!(line.orientations().len(), 2);
+    let ell = Shape::from_rows(&["#.", "#.", "##"]);
+    assert_eq!(ell.orientations().len(), 8);
+    let tee = Shape::from_rows(&["###", ".#."]);
+    assert_eq!(tee.orientations().len(), 4);
+}
+
+/// One present placed in a region, with the cells it covers.
+#[derive(Debug, Clone)]
+struct Placement {
+    shape: usize,
+    cells: Vec<Point>,
+}
+
+/// Backtracking search over the region cells in row-major order. The first undecided
+/// cell is either covered by a present whose first cell lands on it, or left empty
+/// while there are more spare cells than the presents need.
+///
+/// Everything before the current cell is decided and presents only reach a few rows
+/// ahead, so a dead end is fully described by the cell, the occupied cells in that
+/// window and the presents still left. Those states are remembered and never retried.
+struct PackingSearch<'a> {
+    width: usize,
+    height: usize,
+    orientations: &'a [Vec<Shape>],
+    remaining: Vec<usize>,
+    occupied: Vec<bool>,
+    placements: Vec<Placement>,
+    // The longest partial packing seen, to explain regions that don't fit.
+    deepest: Vec<Placement>,
+    spare_cells: usize,
+    window: usize,
+    dead_ends: HashSet<(usize, Vec<u64>, Vec<usize>)>,
+}
+
+impl PackingSearch<'_> {
+    fn state(&self, index: usize) -> (usize, Vec<u64>, Vec<usize>) {
+        let end = (index + self.window).min(self.occupied.len());
+        let window = self.occupied[index..end]
+            .chunks(64)
+            .map(|chunk| {
+                chunk
+                    .iter()
+                    .rev()
+                    .fold(0u64, |bits, &occupied| (bits << 1) | occupied as u64)
+            })
+            .collect();
+        (index, window, self.remaining.clone())
+    }
+
+    fn try_place(&self, shape: &Shape, index: usize) -> Option<Vec<Point>> {
+        let anchor = shape.cells[0];
+        let (x0, y0) = (index % self.width, index / self.width);
+        if x0 < anchor.x {
+            return None;
+        }
+        shape
+            .cells
+            .iter()
+            .map(|p| {
+                let point = Point {
+                    x: x0 + p.x - anchor.x,
+                    y: y0 + p.y - anchor.y,
+                };
+                let free = point.x < self.width
+                    && point.y < self.height
+                    && !self.occupied[point.y * self.width + point.x];
+                free.then_some(point)
+            })
+            .collect()
+    }
+
+    fn search(&mut self, from: usize) -> bool {
+        checkpoint();
+        if self.remaining.iter().all(|&count| count == 0) {
+            return true;
+        }
+        let Some(index) = (from..self.occupied.len()).find(|&i| !self.occupied[i]) else {
+            return false;
+        };
+        let state = self.state(index);
+        if self.dead_ends.contains(&state) {
+            return false;
+        }
+
+        for shape_idx in 0..self.orientations.len() {
+            if self.remaining[shape_idx] == 0 {
+                continue;
+            }
+            for orientation in &self.orientations[shape_idx] {
+                let Some(cells) = self.try_place(orientation, index) else {
+                    continue;
+                };
+                cells
+                    .iter()
+                    .for_each(|p| self.occupied[p.y * self.width + p.x] = true);
+                self.remaining[shape_idx] -= 1;
+                self.placements.push(Placement {
+                    shape: shape_idx,
+                    cells,
+                });
+                if self.placements.len() > self.deepest.len() {
+                    self.deepest = self.placements.clone();
+                }
+                if self.search(index + 1) {
+                    return true;
+                }
+                let placement = self.placements.pop().unwrap();
+                self.remaining[shape_idx] += 1;
+                placement
+                    .cells
+                    .iter()
+                    .for_each(|p| self.occupied[p.y * self.width + p.x] = false);
+            }
+        }
+
+        if self.spare_cells > 0 {
+            self.spare_cells -= 1;
+            if self.search(index + 1) {
+                return true;
+            }
+            self.spare_cells += 1;
+        }
+        self.dead_ends.insert(state);
+        false
+    }
+}
 
 struct Example {
     height: usize,
@@ -8,10 +215,125 @@ struct Example {
 }
 
 impl Example {
-    fn fits(&self) -> bool {
-        let needed = self.counts.iter().sum::<usize>() * 9;
+    /// Places every requested present in the region, or explains why they can't fit.
+    fn pack(&self, shapes: &[Shape]) -> Result<Vec<Placement>, NoFit> {
+        assert_eq!(
+            self.counts.len(),
+            shapes.len(),
+            "Region counts don't match the number of shapes"
+        );
+        let needed: usize = self
+            .counts
+            .iter()
+            .zip(shapes)
+            .map(|(count, shape)| count * shape.cells.len())
+            .sum();
         let available = self.height * self.width;
-        needed <= available
+        if needed > available {
+            return Err(NoFit::TooSmall { needed, available });
+        }
+
+        if let Some(placements) = self.pack_into_boxes(shapes) {
+            return Ok(placements);
+        }
+
+        let orientations: Vec<Vec<Shape>> = shapes.iter().map(Shape::orientations).collect();
+
+        // The memoised window spans whole rows, so keep the rows short by scanning along
+        // the narrower side. Orientations cover every rotation, so transposing is free.
+        let transposed = self.width > self.height;
+        let (width, height) = if transposed {
+            (self.height, self.width)
+        } else {
+            (self.width, self.height)
+        };
+        let mut search = PackingSearch {
+            width,
+            height,
+            orientations: &orientations,
+            remaining: self.counts.clone(),
+            occupied: vec![false; available],
+            placements: Vec::new(),
+            deepest: Vec::new(),
+            spare_cells: available - needed,
+            window: width * shapes.iter().map(Shape::height).max().unwrap_or(1),
+            dead_ends: HashSet::new(),
+        };
+        let fits = search.search(0);
+        let transpose_back = |mut placements: Vec<Placement>| {
+            if transposed {
+                placements
+                    .iter_mut()
+                    .flat_map(|placement| placement.cells.iter_mut())
+                    .for_each(|p| (p.x, p.y) = (p.y, p.x));
+            }
+            placements
+        };
+        if fits {
+            Ok(transpose_back(search.placements))
+        } else {
+            Err(NoFit::Exhausted {
+                deepest: transpose_back(search.deepest),
+            })
+        }
+    }
+
+    /// Shortcut for roomy regions: if every present gets its own bounding-box-sized slot,
+    /// they trivially fit without any search.
+    fn pack_into_boxes(&self, shapes: &[Shape]) -> Option<Vec<Placement>> {
+        let box_width = shapes.iter().map(Shape::width).max().unwrap_or(1);
+        let box_height = shapes.iter().map(Shape::height).max().unwrap_or(1);
+        let columns = self.width / box_width;
+        let slots = columns * (self.height / box_height);
+        if self.counts.iter().sum::<usize>() > slots {
+            return None;
+        }
+
+        let presents = self
+            .counts
+            .iter()
+            .enumerate()
+            .flat_map(|(shape, &count)| std::iter::repeat_n(shape, count));
+        Some(
+            presents
+                .enumerate()
+                .map(|(slot, shape)| {
+                    let (x0, y0) = ((slot % columns) * box_width, (slot / columns) * box_height);
+                    Placement {
+                        shape,
+                        cells: shapes[shape]
+                            .cells
+                            .iter()
+                            .map(|p| Point {
+                                x: x0 + p.x,
+                                y: y0 + p.y,
+                            })
+                            .collect(),
+                    }
+                })
+                .collect(),
+        )
+    }
+}
+
+/// Why the presents of a region don't fit.
+#[derive(Debug)]
+enum NoFit {
+    /// The presents cover more cells than the region has.
+    TooSmall { needed: usize, available: usize },
+    /// The search tried every placement, `deepest` is the most presents it ever placed.
+    Exhausted { deepest: Vec<Placement> },
+}
+
+impl Display for Example {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}x{}: {}",
+            self.width,
+            self.height,
+            self.counts.iter().join(" ")
+        )
     }
 }
 
@@ -34,15 +356,575 @@ impl From<&str> for Example {
     }
 }
 
+/// Splits the input into the shape blocks (`0:` followed by its rows) and the regions.
+fn parse_input(lines: &[String]) -> (Vec<Shape>, Vec<Example>) {
+    let mut shapes: Vec<Shape> = Vec::new();
+    let mut examples: Vec<Example> = Vec::new();
+    let mut shape_rows: Vec<&str> = Vec::new();
+    for line in lines {
+        if line.contains('x') && line.contains(": ") {
+            examples.push(Example::from(line.as_str()));
+        } else if let Some(index) = line.strip_suffix(':') {
+            if !shape_rows.is_empty() {
+                shapes.push(Shape::from_rows(&shape_rows));
+                shape_rows.clear();
+            }
+            assert_eq!(
+                index.trim().parse::<usize>().ok(),
+                Some(shapes.len()),
+                "Shapes should be numbered in order"
+            );
+        } else {
+            shape_rows.push(line.as_str());
+        }
+    }
+    if !shape_rows.is_empty() {
+        shapes.push(Shape::from_rows(&shape_rows));
+    }
+    (shapes, examples)
+}
+
+/// Letter for the n-th placed present: A-Z, then a-z, then digits, then around again.
+fn instance_label(instance: usize) -> char {
+    const LABELS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+    LABELS[instance % LABELS.len()] as char
+}
+
+fn instance_colour(instance: usize) -> String {
+    format!("hsl({:.0},70%,60%)", (instance as f64 * 137.508) % 360.0)
+}
+
+fn render_grid(width: usize, height: usize, placements: &[Placement]) -> String {
+    let mut grid = vec![vec!['.'; width]; height];
+    for (instance, placement) in placements.iter().enumerate() {
+        for p in &placement.cells {
+            grid[p.y][p.x] = instance_label(instance);
+        }
+    }
+    grid.into_iter()
+        .map(|row| row.into_iter().collect::<String>())
+        .join("\n")
+}
+
+#[test]
+fn test_render_grid() {
+    let placements = vec![
+        Placement {
+            shape: 0,
+            cells: vec![Point { x: 0, y: 0 }, Point { x: 1, y: 0 }],
+        },
+        Placement {
+            shape: 1,
+            cells: vec![Point { x: 2, y: 1 }],
+        },
+    ];
+    assert_eq!(render_grid(3, 2, &placements), "AA.\n..B");
+}
+
+/// Text report for one region: the packing if it fits, otherwise why not.
+fn render_report(example: &Example, result: &Result<Vec<Placement>, NoFit>) -> String {
+    let total: usize = example.counts.iter().sum();
+    match result {
+        Ok(placements) => format!(
+            "{example} fits\n{}",
+            render_grid(example.width, example.height, placements)
+        ),
+        Err(NoFit::TooSmall { needed, available }) => format!(
+            "{example} doesn't fit: the presents need {needed} cells but the region has {available}"
+        ),
+        Err(NoFit::Exhausted { deepest }) => {
+            let mut missing = example.counts.clone();
+            deepest.iter().for_each(|p| missing[p.shape] -= 1);
+            let missing = missing
+                .iter()
+                .enumerate()
+                .filter(|&(_, &count)| count > 0)
+                .map(|(shape, count)| format!("{count} of shape {shape}"))
+                .join(", ");
+            format!(
+                "{example} doesn't fit: placed at most {} of {total} presents, left over {missing}\n{}",
+                deepest.len(),
+                render_grid(example.width, example.height, deepest)
+            )
+        }
+    }
+}
+
+const SVG_CELL: f64 = 12.0;
+const SVG_MARGIN: f64 = 20.0;
+
+/// All regions stacked top to bottom, each present in its own colour. Regions that
+/// don't fit show the deepest partial packing the search reached.
+fn render_svg(examples: &[Example], results: &[Result<Vec<Placement>, NoFit>]) -> Svg {
+    let width = examples.iter().map(|e| e.width).max().unwrap_or(0) as f64 * SVG_CELL;
+    let height: f64 = examples
+        .iter()
+        .map(|e| e.height as f64 * SVG_CELL + SVG_MARGIN * 2.0)
+        .sum();
+    let mut svg = Svg::new(width + SVG_MARGIN * 2.0, height + SVG_MARGIN);
+
+    let mut top = SVG_MARGIN;
+    for (example, result) in examples.iter().zip(results) {
+        let (status, placements): (&str, &[Placement]) = match result {
+            Ok(placements) => ("fits", placements),
+            Err(NoFit::TooSmall { .. }) => ("doesn't fit, too small", &[]),
+            Err(NoFit::Exhausted { deepest }) => ("doesn't fit, deepest packing", deepest),
+        };
+        svg.text(
+            SVG_MARGIN,
+            top + SVG_MARGIN / 2.0,
+            &format!("{example} ({status})"),
+            "font-family:monospace;font-size:12px",
+        );
+        top += SVG_MARGIN;
+        svg.rect(
+            SVG_MARGIN,
+            top,
+            example.width as f64 * SVG_CELL,
+            example.height as f64 * SVG_CELL,
+            "fill:#f4f4f4;stroke:#444;stroke-width:1",
+            None,
+        );
+        for (instance, placement) in placements.iter().enumerate() {
+            let style = format!(
+                "fill:{};stroke:#222;stroke-width:0.5",
+                instance_colour(instance)
+            );
+            let title = format!("{} (shape {})", instance_label(instance), placement.shape);
+            for p in &placement.cells {
+                svg.rect(
+                    SVG_MARGIN + p.x as f64 * SVG_CELL,
+                    top + p.y as f64 * SVG_CELL,
+                    SVG_CELL,
+                    SVG_CELL,
+                    &style,
+                    Some(&title),
+                );
+            }
+        }
+        top += example.height as f64 * SVG_CELL + SVG_MARGIN;
+    }
+    svg
+}
+
+#[test]
+fn test_pack_examples() {
+    let lines = read_input_lines(12, Some("test"));
+    let (shapes, examples) = parse_input(&lines);
+    assert_eq!(shapes.len(), 6);
+    let fits: Vec<bool> = examples
+        .iter()
+        .map(|e| {
+            let placements = e.pack(&shapes);
+            if let Ok(placements) = &placements {
+                let covered: Vec<&Point> = placements.iter().flat_map(|p| &p.cells).collect();
+                assert_eq!(covered.len(), covered.iter().unique().count());
+                assert!(covered.iter().all(|p| p.x < e.width && p.y < e.height));
+            }
+            placements.is_ok()
+        })
+        .collect();
+    assert_eq!(fits, vec![true, true, false]);
+}
+
+#[test]
+fn test_report_for_region_that_does_not_fit() {
+    let lines = read_input_lines(12, Some("test"));
+    let (shapes, examples) = parse_input(&lines);
+    let report = render_report(&examples[2], &examples[2].pack(&shapes));
+    assert!(report.starts_with("12x5: 1 0 1 0 3 2 doesn't fit: placed at most 6 of 7 presents"));
+    assert_eq!(report.lines().count(), 6);
+}
+
+/// Six random 3×3 shapes and `size` regions. Like the real input, most regions either leave
+/// room for every present in its own 3×3 box or ask for more cells than they have. The rest
+/// are small and filled close to their cells, so only the packing search can decide them.
+pub fn generate(rng: &mut Rng, size: usize) -> Vec<String> {
+    const SHAPES: usize = 6;
+    let mut lines = Vec::new();
+    let mut shape_cells = Vec::with_capacity(SHAPES);
+    for index in 0..SHAPES {
+        let rows: Vec<String> = loop {
+            let rows: Vec<String> = (0..3)
+                .map(|_| {
+                    (0..3)
+                        .map(|_| if rng.chance(0.7) { '#' } else { '.' })
+                        .collect()
+                })
+                .collect();
+            // Keep the full 3×3 extent so the shape is exactly as big as its box.
+            let spans_rows = rows.iter().all(|row| row.contains('#'));
+            let spans_columns = (0..3).all(|x| rows.iter().any(|row| row.as_bytes()[x] == b'#'));
+            if spans_rows && spans_columns {
+                break rows;
+            }
+        };
+        shape_cells.push(
+            rows.iter()
+                .map(|row| row.matches('#').count())
+                .sum::<usize>(),
+        );
+        lines.push(format!("{}:", index));
+        lines.extend(rows);
+        lines.push(String::new());
+    }
+
+    for _ in 0..size {
+        let tight = rng.chance(0.3);
+        let sides = if tight { 4..=7 } else { 6..=50 };
+        let width = rng.range(sides.clone()) as usize;
+        let height = rng.range(sides) as usize;
+        let mut counts = [0usize; SHAPES];
+        if tight {
+            let mut cells = 0;
+            loop {
+                let shape = rng.index(SHAPES);
+                if cells + shape_cells[shape] > width * height {
+                    break;
+                }
+                counts[shape] += 1;
+                cells += shape_cells[shape];
+            }
+        } else if rng.chance(0.5) {
+            for _ in 0..(width / 3) * (height / 3) {
+                counts[rng.index(SHAPES)] += 1;
+            }
+        } else {
+            let mut cells = 0;
+            while cells <= width * height {
+                let shape = rng.index(SHAPES);
+                counts[shape] += 1;
+                cells += shape_cells[shape];
+            }
+        }
+        lines.push(format!("{}x{}: {}", width, height, counts.iter().join(" ")));
+    }
+    lines
+}
+
+#[test]
+fn test_generated_regions_need_the_search() {
+    let lines: Vec<String> = generate(&mut Rng::new(0), 20)
+        .into_iter()
+        .filter(|line| !line.is_empty())
+        .collect();
+    let (shapes, examples) = parse_input(&lines);
+    let searched = examples
+        .iter()
+        .filter(|e| {
+            let needed: usize = e
+                .counts
+                .iter()
+                .zip(&shapes)
+                .map(|(c, s)| c * s.cells.len())
+                .sum();
+            needed <= e.width * e.height && e.pack_into_boxes(&shapes).is_none()
+        })
+        .count();
+    assert!(searched > 0);
+    assert_eq!(solve(1, &lines), solve_naive(1, &lines));
+}
+
+/// Number of regions that fit their presents; the puzzle has a single part.
+pub fn solve(_part: u8, lines: &[String]) -> String {
+    let (shapes, examples) = parse_input(lines);
+    examples
+        .iter()
+        .filter(|e| e.pack(&shapes).is_ok())
+        .count()
+        .to_string()
+}
+
+/// Reference answer. The two cheap bounds decide most regions: too few cells never fit, and
+/// one bounding box per present always does. Anything in between gets a plain backtracking
+/// search over the cells, without the memo or the transposition of the fast solver.
+pub fn solve_naive(_part: u8, lines: &[String]) -> String {
+    let mut shapes: Vec<Vec<(usize, usize)>> = Vec::new();
+    let mut regions: Vec<(usize, usize, Vec<usize>)> = Vec::new();
+    for line in lines {
+        if let Some((size, counts)) = line.split_once(": ") {
+            let (width, height) = size.split_once('x').unwrap();
+            let counts = counts
+                .split_whitespace()
+                .map(|c| c.parse().unwrap())
+                .collect();
+            regions.push((width.parse().unwrap(), height.parse().unwrap(), counts));
+        } else if line.ends_with(':') {
+            shapes.push(Vec::new());
+        } else {
+            let shape = shapes.last_mut().unwrap();
+            let y = shape.iter().map(|&(_, y)| y + 1).max().unwrap_or(0);
+            shape.extend(line.match_indices('#').map(|(x, _)| (x, y)));
+        }
+    }
+
+    let normalise = |cells: Vec<(usize, usize)>| -> Vec<(usize, usize)> {
+        let min_x = cells.iter().map(|c| c.0).min().unwrap();
+        let min_y = cells.iter().map(|c| c.1).min().unwrap();
+        cells
+            .into_iter()
+            .map(|(x, y)| (x - min_x, y - min_y))
+            .sorted_by_key(|&(x, y)| (y, x))
+            .collect()
+    };
+    let orientations: Vec<Vec<Vec<(usize, usize)>>> = shapes
+        .iter()
+        .map(|cells| {
+            let mut all = Vec::new();
+            let mut current = cells.clone();
+            for _ in 0..4 {
+                // Rotate a quarter turn, then add the shape and its mirror image.
+                let max_y = current.iter().map(|c| c.1).max().unwrap();
+                current = normalise(current.iter().map(|&(x, y)| (max_y - y, x)).collect());
+                let max_x = current.iter().map(|c| c.0).max().unwrap();
+                all.push(current.clone());
+                all.push(normalise(
+                    current.iter().map(|&(x, y)| (max_x - x, y)).collect(),
+                ));
+            }
+            all.into_iter().unique().collect()
+        })
+        .collect();
+
+    // Covers the first free cell in reading order with some present, or leaves it empty
+    // while there are spare cells.
+    fn place(
+        counts: &mut [usize],
+        orientations: &[Vec<Vec<(usize, usize)>>],
+        occupied: &mut [Vec<bool>],
+        spare: usize,
+    ) -> bool {
+        checkpoint();
+        if counts.iter().all(|&c| c == 0) {
+            return true;
+        }
+        let (height, width) = (occupied.len(), occupied[0].len());
+        let Some((x0, y0)) = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .find(|&(x, y)| !occupied[y][x])
+        else {
+            return false;
+        };
+        for shape in 0..counts.len() {
+            if counts[shape] == 0 {
+                continue;
+            }
+            for cells in &orientations[shape] {
+                // Cells are in reading order, so the first one goes on the free cell.
+                let (anchor_x, anchor_y) = cells[0];
+                if cells.iter().any(|&(x, _)| x + x0 < anchor_x) {
+                    continue;
+                }
+                let at = |&(x, y): &(usize, usize)| (x + x0 - anchor_x, y + y0 - anchor_y);
+                let fits = cells
+                    .iter()
+                    .map(at)
+                    .all(|(x, y)| x < width && y < height && !occupied[y][x]);
+                if !fits {
+                    continue;
+                }
+                cells
+                    .iter()
+                    .map(at)
+                    .for_each(|(x, y)| occupied[y][x] = true);
+                counts[shape] -= 1;
+                let placed = place(counts, orientations, occupied, spare);
+                counts[shape] += 1;
+                cells
+                    .iter()
+                    .map(at)
+                    .for_each(|(x, y)| occupied[y][x] = false);
+                if placed {
+                    return true;
+                }
+            }
+        }
+        if spare == 0 {
+            return false;
+        }
+        occupied[y0][x0] = true;
+        let placed = place(counts, orientations, occupied, spare - 1);
+        occupied[y0][x0] = false;
+        placed
+    }
+
+    let box_side = |axis: fn(&(usize, usize)) -> usize| {
+        shapes
+            .iter()
+            .flat_map(|cells| cells.iter().map(axis))
+            .max()
+            .unwrap_or(0)
+            + 1
+    };
+    let (box_width, box_height) = (box_side(|c| c.0), box_side(|c| c.1));
+    regions
+        .iter()
+        .filter(|(width, height, counts)| {
+            let needed: usize = counts.iter().zip(&shapes).map(|(c, s)| c * s.len()).sum();
+            let presents: usize = counts.iter().sum();
+            if needed > width * height {
+                false
+            } else if presents <= (width / box_width) * (height / box_height) {
+                true
+            } else {
+                place(
+                    &mut counts.clone(),
+                    &orientations,
+                    &mut vec![vec![false; *width]; *height],
+                    width * height - needed,
+                )
+            }
+        })
+        .count()
+        .to_string()
+}
+
+/// Numbered shape blocks of `#` and `.` rows, then `WxH: counts` regions with one count per
+/// shape.
+pub fn validate(lines: &[String]) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    // Header line and number of `#` cells of every shape so far.
+    let mut shapes: Vec<(usize, usize)> = Vec::new();
+    let mut regions: Vec<(usize, usize)> = Vec::new();
+    let is_size = |size: &str| {
+        size.split_once('x').is_some_and(|(width, height)| {
+            [width, height]
+                .iter()
+                .all(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+        })
+    };
+    for (line, row) in numbered(lines) {
+        let first = row.split_whitespace().next().unwrap_or("");
+        if is_size(first.trim_end_matches(':')) || (row.contains('x') && row.contains(": ")) {
+            let (size, counts) = match row.split_once(':') {
+                Some((size, counts)) => (size.trim(), counts),
+                None => {
+                    problems.push(Problem::at(
+                        line,
+                        format!("Region size '{}' should be followed by ':'", first),
+                    ));
+                    row.trim_start().split_at(first.len())
+                }
+            };
+            match size.split_once('x') {
+                Some((width, height)) => {
+                    parse::<usize>(line, width, "a width", &mut problems);
+                    parse::<usize>(line, height, "a height", &mut problems);
+                }
+                None => problems.push(Problem::at(
+                    line,
+                    format!("'{}' is not a WxH region size", size),
+                )),
+            }
+            let count = counts.split_whitespace().count();
+            for present_count in counts.split_whitespace() {
+                parse::<usize>(line, present_count, "a present count", &mut problems);
+            }
+            regions.push((line, count));
+        } else if let Some(index) = row.strip_suffix(':') {
+            if let Some(&(first_region, _)) = regions.first() {
+                problems.push(Problem::at(
+                    line,
+                    format!("Shape after the regions started on line {}", first_region),
+                ));
+            }
+            if index.trim().parse::<usize>().ok() != Some(shapes.len()) {
+                problems.push(Problem::at(
+                    line,
+                    format!("Shape '{}' should be numbered {}", index, shapes.len()),
+                ));
+            }
+            shapes.push((line, 0));
+        } else if let Some((_, cells)) = shapes.last_mut() {
+            if let Some(c) = row.chars().find(|&c| c != '#' && c != '.') {
+                problems.push(Problem::at(
+                    line,
+                    format!("Invalid shape character '{}'", c),
+                ));
+            }
+            *cells += row.matches('#').count();
+        } else {
+            problems.push(Problem::at(
+                line,
+                "Expected a shape header like '0:' or a region like '4x4: 0 1'",
+            ));
+        }
+    }
+
+    for &(line, cells) in &shapes {
+        if cells == 0 {
+            problems.push(Problem::at(line, "Shape has no '#' cells"));
+        }
+    }
+    if regions.is_empty() {
+        problems.push(Problem::input("There are no regions"));
+    }
+    for (line, count) in regions {
+        if count != shapes.len() {
+            problems.push(Problem::at(
+                line,
+                format!("{} present counts for {} shapes", count, shapes.len()),
+            ));
+        }
+    }
+    problems
+}
+
+#[test]
+fn test_validate() {
+    let lines = [
+        "0:",
+        "###",
+        "#.#",
+        "",
+        "2:",
+        "...",
+        "4x4: 0 2",
+        "1:",
+        "#",
+        "12x5: 1 0 1 x",
+        "4x4 1 0 1",
+    ]
+    .map(String::from);
+    let problems = validate(&lines).iter().map(|p| p.to_string()).collect_vec();
+    assert_eq!(
+        problems,
+        [
+            "line 5: Shape '2' should be numbered 1",
+            "line 8: Shape after the regions started on line 7",
+            "line 8: Shape '1' should be numbered 2",
+            "line 10: 'x' is not a present count",
+            "line 11: Region size '4x4' should be followed by ':'",
+            "line 5: Shape has no '#' cells",
+            "line 7: 2 present counts for 3 shapes",
+            "line 10: 4 present counts for 3 shapes",
+        ]
+    );
+}
+
 pub fn main(args: &Args) {
     let lines = read_input_lines(args.day as u32, args.input_tag.as_deref());
+    let (shapes, examples) = parse_input(&lines);
 
-    let examples = lines
-        .iter()
-        .filter(|l| l.contains(": "))
-        .map(|l| Example::from(l.as_str()))
-        .collect::<Vec<Example>>();
+    let results: Vec<_> = examples.iter().map(|e| e.pack(&shapes)).collect();
+
+    if args.render {
+        for (example, result) in examples.iter().zip(&results) {
+            println!("{}\n", render_report(example, result));
+        }
+    }
+    if let Some(path) = &args.svg {
+        render_svg(&examples, &results)
+            .write_to(path)
+            .unwrap_or_else(|e| panic!("Unable to write SVG to {}: {e}", path.display()));
+        info!("Wrote packings to {}", path.display());
+    }
 
-    let fit_count = examples.iter().filter(|e| e.fits()).count();
+    let fit_count = results.iter().filter(|r| r.is_ok()).count();
     println!("Number of examples that fit: {}", fit_count);
 }
+
+#[test]
+fn test_examples() {
+    crate::days::check_example(12, "test");
+}
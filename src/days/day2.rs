@@ -1,6 +1,10 @@
 use crate::args::Args;
 use crate::utils::input::read_input_lines;
 use crate::utils::integers::{count_digits, divisors};
+use crate::utils::rng::Rng;
+use crate::utils::validate::{Problem, numbered, parse};
+use itertools::Itertools;
+use std::collections::BTreeSet;
 
 #[derive(Debug)]
 struct Range {
@@ -69,7 +73,7 @@ impl Range {
 }
 
 fn merge_ranges(mut ranges: Vec<Range>) -> Vec<Range> {
-    ranges.sort_by(|a, b| a.start.cmp(&b.start));
+    ranges.sort_by_key(|a| a.start);
 
     let mut merged_ranges = Vec::<Range>::new();
 
@@ -106,6 +110,41 @@ fn is_invalid_pt2(id: i64) -> bool {
     false
 }
 
+#[test]
+fn test_merge_ranges_keeps_the_same_ids() {
+    use crate::utils::property::check;
+    check(
+        "merged ranges are sorted, disjoint and cover the same IDs",
+        500,
+        |g| {
+            g.vec(12, |g| {
+                let start = g.range(0..=100);
+                (start, start + g.range(0..=20))
+            })
+        },
+        |ranges| {
+            let input: Vec<Range> = ranges
+                .iter()
+                .map(|&(start, end)| Range { start, end })
+                .collect();
+            let ids: BTreeSet<i64> = input.iter().flatten().collect();
+            let merged = merge_ranges(input);
+            if let Some((a, b)) = merged
+                .iter()
+                .tuple_windows()
+                .find(|(a, b)| a.end >= b.start)
+            {
+                return Err(format!("{:?} is not before {:?}", a, b));
+            }
+            let merged_ids: BTreeSet<i64> = merged.iter().flatten().collect();
+            if merged_ids != ids {
+                return Err(format!("merged into {:?}", merged));
+            }
+            Ok(())
+        },
+    );
+}
+
 #[test]
 fn test_is_invalid_pt2() {
     assert!(is_invalid_pt2(1212));
@@ -116,7 +155,7 @@ fn test_is_invalid_pt2() {
     assert!(!is_invalid_pt2(1));
 }
 
-fn main_part1(ranges: &[Range]) {
+fn main_part1(ranges: &[Range]) -> i64 {
     let mut invalid_ids_sum = 0;
     for range in ranges {
         for id in range.into_iter() {
@@ -127,10 +166,10 @@ fn main_part1(ranges: &[Range]) {
         }
     }
 
-    println!("Invalid IDs sum: {invalid_ids_sum}");
+    invalid_ids_sum
 }
 
-fn main_part2(ranges: &[Range]) {
+fn main_part2(ranges: &[Range]) -> i64 {
     let mut invalid_ids_sum = 0;
     for range in ranges {
         for id in range.into_iter() {
@@ -139,20 +178,123 @@ fn main_part2(ranges: &[Range]) {
             }
         }
     }
-    println!("Invalid IDs sum for part 2: {invalid_ids_sum}");
+    invalid_ids_sum
 }
 
-pub fn main(args: &Args) {
-    let lines = read_input_lines(args.day as u32, args.input_tag.as_deref());
-    let mut ranges: Vec<Range> = lines[0]
+fn read_ranges(lines: &[String]) -> Vec<Range> {
+    let ranges: Vec<Range> = lines[0]
         .split(',')
         .map(Range::from_str)
         .collect::<Vec<Range>>();
+    merge_ranges(ranges)
+}
 
-    ranges = merge_ranges(ranges);
+/// One line of `size` ID ranges, each at most 100k IDs wide.
+pub fn generate(rng: &mut Rng, size: usize) -> Vec<String> {
+    let ranges = (0..size)
+        .map(|_| {
+            let digits = rng.range(1..=10) as u32;
+            let start = rng.range(1..=10i64.pow(digits) - 1);
+            let end = start + rng.range(0..=(start / 2).min(100_000));
+            format!("{}-{}", start, end)
+        })
+        .join(",");
+    vec![ranges]
+}
+
+pub fn solve(part: u8, lines: &[String]) -> String {
+    let ranges = read_ranges(lines);
+    match part {
+        1 => main_part1(&ranges),
+        _ => main_part2(&ranges),
+    }
+    .to_string()
+}
+
+/// Reference answer from the raw, unmerged ranges: every distinct ID is checked by comparing
+/// its decimal string with a repeated prefix.
+pub fn solve_naive(part: u8, lines: &[String]) -> String {
+    let ids: BTreeSet<i64> = lines[0]
+        .split(',')
+        .map(Range::from_str)
+        .flat_map(|range| range.start..=range.end)
+        .collect();
+    ids.into_iter()
+        .filter(|id| {
+            let digits = id.to_string();
+            let max_repeats = if part == 1 { 2 } else { digits.len() };
+            (2..=max_repeats).any(|repeats| {
+                digits.len() % repeats == 0
+                    && (part != 1 || repeats == 2)
+                    && digits[..digits.len() / repeats].repeat(repeats) == digits
+            })
+        })
+        .sum::<i64>()
+        .to_string()
+}
+
+/// A single line of comma-separated `start-end` ID ranges with `start <= end`.
+pub fn validate(lines: &[String]) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    let rows = numbered(lines);
+    match rows.len() {
+        0 => problems.push(Problem::input("There are no ID ranges")),
+        1 => {}
+        _ => problems.extend(
+            rows[1..]
+                .iter()
+                .map(|&(line, _)| Problem::at(line, "The ranges should all be on one line")),
+        ),
+    }
+    for &(line, row) in &rows {
+        for range in row.split(',').map(str::trim).filter(|r| !r.is_empty()) {
+            let Some((start, end)) = range.split_once('-') else {
+                problems.push(Problem::at(line, format!("'{}' is not a range", range)));
+                continue;
+            };
+            let start = parse::<i64>(line, start, "an ID", &mut problems);
+            let end = parse::<i64>(line, end, "an ID", &mut problems);
+            if let (Some(start), Some(end)) = (start, end)
+                && start > end
+            {
+                problems.push(Problem::at(
+                    line,
+                    format!("Range {} ends before it starts", range),
+                ));
+            }
+        }
+    }
+    problems
+}
+
+#[test]
+fn test_validate() {
+    let lines = ["11-22,95-1x,30-20,7", "1-2"].map(String::from);
+    let problems = validate(&lines).iter().map(|p| p.to_string()).collect_vec();
+    assert_eq!(
+        problems,
+        [
+            "line 2: The ranges should all be on one line",
+            "line 1: '1x' is not an ID",
+            "line 1: Range 30-20 ends before it starts",
+            "line 1: '7' is not a range",
+        ]
+    );
+}
+
+pub fn main(args: &Args) {
+    let lines = read_input_lines(args.day as u32, args.input_tag.as_deref());
+    let ranges = read_ranges(&lines);
     if args.part == 1 {
-        main_part1(&ranges);
+        let invalid_ids_sum = main_part1(&ranges);
+        println!("Invalid IDs sum: {invalid_ids_sum}");
     } else {
-        main_part2(&ranges);
+        let invalid_ids_sum = main_part2(&ranges);
+        println!("Invalid IDs sum for part 2: {invalid_ids_sum}");
     }
 }
+
+#[test]
+fn test_examples() {
+    crate::days::check_example(2, "test");
+}
@@ -1,5 +1,7 @@
 use crate::args::Args;
 use crate::utils::input::read_input_lines;
+use crate::utils::rng::Rng;
+use crate::utils::validate::{Problem, numbered};
 use std::collections::HashMap;
 
 struct Battery {
@@ -56,24 +58,117 @@ fn find_max_comb_impl(
     Some(*result)
 }
 
-pub fn main(args: &Args) {
-    let combs = if args.part == 1 { 2 } else { 12 };
-
-    let battery_lines: Vec<Vec<Battery>> =
-        read_input_lines(args.day as u32, args.input_tag.as_deref())
-            .iter()
-            .map(|line| {
-                line.chars()
-                    .map(|c| c.to_digit(10).unwrap().into())
-                    .collect::<Vec<Battery>>()
-            })
-            .collect();
+fn read_battery_lines(lines: &[String]) -> Vec<Vec<Battery>> {
+    lines
+        .iter()
+        .map(|line| {
+            line.chars()
+                .map(|c| c.to_digit(10).unwrap().into())
+                .collect::<Vec<Battery>>()
+        })
+        .collect()
+}
 
-    println!("Battery count {}, combs: {}", battery_lines[0].len(), combs);
+fn combs_for_part(part: u8) -> usize {
+    if part == 1 { 2 } else { 12 }
+}
 
-    let total_voltage: i64 = battery_lines
+fn total_voltage(battery_lines: &[Vec<Battery>], combs: usize) -> i64 {
+    battery_lines
         .iter()
         .map(|batteries| find_max_comb(batteries, combs).unwrap())
-        .sum();
-    println!("Total voltage: {}", total_voltage);
+        .sum()
+}
+
+/// `size` banks of 100 batteries each.
+pub fn generate(rng: &mut Rng, size: usize) -> Vec<String> {
+    (0..size)
+        .map(|_| (0..100).map(|_| rng.range(1..=9).to_string()).collect())
+        .collect()
+}
+
+pub fn solve(part: u8, lines: &[String]) -> String {
+    total_voltage(&read_battery_lines(lines), combs_for_part(part)).to_string()
+}
+
+/// Reference answer on the digit strings: every pair for part 1, and for part 2 the greedy
+/// choice of the largest digit that still leaves enough batteries for the rest.
+pub fn solve_naive(part: u8, lines: &[String]) -> String {
+    let combs = combs_for_part(part);
+    lines
+        .iter()
+        .map(|line| {
+            let digits = line.as_bytes();
+            if combs == 2 {
+                (0..digits.len())
+                    .flat_map(|i| (i + 1..digits.len()).map(move |j| (i, j)))
+                    .map(|(i, j)| ((digits[i] - b'0') * 10 + digits[j] - b'0') as i64)
+                    .max()
+                    .unwrap()
+            } else {
+                let mut joltage = 0i64;
+                let mut from = 0;
+                for left in (0..combs).rev() {
+                    // The first occurrence of the largest digit keeps the most options open.
+                    let window = &digits[from..digits.len() - left];
+                    let best = *window.iter().max().unwrap();
+                    from += window.iter().position(|&d| d == best).unwrap() + 1;
+                    joltage = joltage * 10 + (best - b'0') as i64;
+                }
+                joltage
+            }
+        })
+        .sum::<i64>()
+        .to_string()
+}
+
+/// Every bank is a line of digits, long enough to turn on the 12 batteries part 2 needs.
+pub fn validate(lines: &[String]) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    let banks = numbered(lines);
+    if banks.is_empty() {
+        problems.push(Problem::input("There are no battery banks"));
+    }
+    let needed = combs_for_part(2);
+    for (line, bank) in banks {
+        if let Some(c) = bank.chars().find(|c| !c.is_ascii_digit()) {
+            problems.push(Problem::at(line, format!("Invalid battery '{}'", c)));
+        } else if bank.len() < needed {
+            problems.push(Problem::at(
+                line,
+                format!("Only {} batteries, part 2 turns on {}", bank.len(), needed),
+            ));
+        }
+    }
+    problems
+}
+
+#[test]
+fn test_validate() {
+    let lines = ["987654321111111", "98765432111a111", "12345"].map(String::from);
+    let problems: Vec<String> = validate(&lines).iter().map(|p| p.to_string()).collect();
+    assert_eq!(
+        problems,
+        [
+            "line 2: Invalid battery 'a'",
+            "line 3: Only 5 batteries, part 2 turns on 12",
+        ]
+    );
+}
+
+pub fn main(args: &Args) {
+    let combs = combs_for_part(args.part);
+    let battery_lines = read_battery_lines(&read_input_lines(
+        args.day as u32,
+        args.input_tag.as_deref(),
+    ));
+
+    println!("Battery count {}, combs: {}", battery_lines[0].len(), combs);
+
+    println!("Total voltage: {}", total_voltage(&battery_lines, combs));
+}
+
+#[test]
+fn test_examples() {
+    crate::days::check_example(3, "test");
 }
@@ -1,7 +1,14 @@
-use crate::args::Args;
+use crate::args::{Args, ExportFormat};
 use crate::utils::input::read_input_lines;
 use crate::utils::point::Point;
-use std::collections::HashMap;
+use crate::utils::ppm::plain_ppm;
+use crate::utils::rng::Rng;
+use crate::utils::validate::{Problem, check_grid, numbered};
+use itertools::Itertools;
+use log::info;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::time::Duration;
 
 #[derive(Debug, Eq, PartialEq, Hash, Copy, Clone)]
 enum Cell {
@@ -78,24 +85,189 @@ impl Grid {
     }
 }
 
-pub fn main(args: &Args) {
-    let lines = read_input_lines(args.day as u32, args.input_tag.as_deref())
+/// The grid before a removal round, with the rolls removed in that round as `x`.
+fn render_round(grid: &Grid, removed: &[Point]) -> String {
+    let mut rows: Vec<Vec<char>> = grid
+        .cells
         .iter()
-        .map(|line| line.chars().map(Cell::from).collect::<Vec<Cell>>())
-        .collect::<Vec<Vec<Cell>>>();
+        .map(|row| {
+            row.iter()
+                .map(|cell| match cell {
+                    Cell::Empty => '.',
+                    Cell::Paper => '@',
+                })
+                .collect()
+        })
+        .collect();
+    for p in removed {
+        rows[p.y][p.x] = 'x';
+    }
+    rows.into_iter().map(String::from_iter).join("\n")
+}
+
+/// Same as [`render_round`] as an image: paper is grey and removed rolls are red.
+fn round_ppm(grid: &Grid, removed: &HashSet<Point>) -> String {
+    let (width, height) = (grid.cells[0].len(), grid.cells.len());
+    plain_ppm(width, height, PPM_SCALE, |x, y| {
+        if removed.contains(&Point { x, y }) {
+            (220, 40, 40)
+        } else if grid.cells[y][x] == Cell::Paper {
+            (110, 110, 110)
+        } else {
+            (255, 255, 255)
+        }
+    })
+}
+
+const PPM_SCALE: usize = 4;
+
+#[test]
+fn test_render_round() {
+    let lines = ["..@@.", "@@@.@", "..@.."];
+    let grid = Grid::new(
+        lines
+            .iter()
+            .map(|line| line.chars().map(Cell::from).collect())
+            .collect(),
+    );
+    let mut pickable = grid.find_pickable_papers();
+    pickable.sort();
+    assert_eq!(render_round(&grid, &pickable), "..xx.\nx@@.x\n..x..");
+}
+
+fn read_grid(lines: &[String]) -> Grid {
+    Grid::new(
+        lines
+            .iter()
+            .map(|line| line.chars().map(Cell::from).collect::<Vec<Cell>>())
+            .collect(),
+    )
+}
+
+/// Rolls removed over all rounds until nothing is accessible any more. `on_round` sees
+/// the grid and the rolls about to go in every round, the last one with nothing left too.
+fn remove_all(mut grid: Grid, mut on_round: impl FnMut(&Grid, &[Point])) -> usize {
+    let mut removed = 0;
+    loop {
+        let pickable = grid.find_pickable_papers();
+        on_round(&grid, &pickable);
+        if pickable.is_empty() {
+            return removed;
+        }
+        removed += pickable.len();
+        grid = grid.clone_with_removed(&pickable);
+    }
+}
+
+/// A `size`×`size` grid where roughly two thirds of the cells hold a roll.
+pub fn generate(rng: &mut Rng, size: usize) -> Vec<String> {
+    (0..size)
+        .map(|_| {
+            (0..size)
+                .map(|_| if rng.chance(0.65) { '@' } else { '.' })
+                .collect()
+        })
+        .collect()
+}
+
+pub fn solve(part: u8, lines: &[String]) -> String {
+    let grid = read_grid(lines);
+    match part {
+        1 => grid.find_pickable_papers().len(),
+        _ => remove_all(grid, |_, _| {}),
+    }
+    .to_string()
+}
+
+/// Reference answer on the raw characters. Part 2 removes one roll at a time in reading
+/// order; removals only ever free more rolls, so the total matches the round-based removal.
+pub fn solve_naive(part: u8, lines: &[String]) -> String {
+    let mut grid: Vec<Vec<u8>> = lines.iter().map(|line| line.as_bytes().to_vec()).collect();
+    let accessible = |grid: &[Vec<u8>], x: usize, y: usize| {
+        let neighbours = (y.saturating_sub(1)..=y + 1)
+            .flat_map(|ny| (x.saturating_sub(1)..=x + 1).map(move |nx| (nx, ny)))
+            .filter(|&(nx, ny)| (nx, ny) != (x, y))
+            .filter(|&(nx, ny)| grid.get(ny).and_then(|row| row.get(nx)) == Some(&b'@'))
+            .count();
+        grid[y][x] == b'@' && neighbours < 4
+    };
+
+    let mut removed = 0;
+    loop {
+        let mut next = (0..grid.len())
+            .flat_map(|y| (0..grid[y].len()).map(move |x| (x, y)))
+            .filter(|&(x, y)| accessible(&grid, x, y));
+        if part == 1 {
+            return next.count().to_string();
+        }
+        let Some((x, y)) = next.next() else {
+            return removed.to_string();
+        };
+        grid[y][x] = b'.';
+        removed += 1;
+    }
+}
+
+/// The diagram is a rectangle of `.` and `@`.
+pub fn validate(lines: &[String]) -> Vec<Problem> {
+    check_grid(&numbered(lines), ".@")
+}
 
-    let mut grid = Grid::new(lines);
+#[test]
+fn test_validate() {
+    assert!(validate(&read_input_lines(4, Some("test"))).is_empty());
+    let lines = ["..@", "@#."].map(String::from);
+    assert_eq!(
+        validate(&lines)[0].to_string(),
+        "line 2: Invalid cell character '#' in column 2"
+    );
+}
+
+pub fn main(args: &Args) {
+    let grid = read_grid(&read_input_lines(
+        args.day as u32,
+        args.input_tag.as_deref(),
+    ));
 
     if args.part == 1 {
         println!("Answer: {}", grid.find_pickable_papers().len());
     } else if args.part == 2 {
-        let mut removed = 0;
-        let mut last_removed = 1;
-        while last_removed > 0 {
-            let pickable = grid.find_pickable_papers();
-            last_removed = pickable.len();
-            removed += last_removed;
-            grid = grid.clone_with_removed(&pickable);
+        let frames_dir = (args.export == Some(ExportFormat::Ppm)).then(|| {
+            let dir = args
+                .output
+                .clone()
+                .unwrap_or_else(|| PathBuf::from("day04_frames"));
+            std::fs::create_dir_all(&dir)
+                .unwrap_or_else(|e| panic!("Unable to create {}: {e}", dir.display()));
+            dir
+        });
+
+        let mut removed_before = 0;
+        let mut round = 0;
+        let removed = remove_all(grid, |grid, pickable| {
+            if args.animate {
+                // Clear the terminal and move the cursor home before drawing the frame.
+                print!("\x1b[2J\x1b[H");
+                println!(
+                    "Round {}: removing {} rolls, {} removed before",
+                    round,
+                    pickable.len(),
+                    removed_before
+                );
+                println!("{}", render_round(grid, pickable));
+                std::thread::sleep(Duration::from_millis(args.frame_delay_ms));
+            }
+            if let Some(dir) = &frames_dir {
+                let path = dir.join(format!("round_{:03}.ppm", round));
+                let removed_set: HashSet<Point> = pickable.iter().copied().collect();
+                std::fs::write(&path, round_ppm(grid, &removed_set))
+                    .unwrap_or_else(|e| panic!("Unable to write {}: {e}", path.display()));
+            }
+            removed_before += pickable.len();
+            round += 1;
+        });
+        if let Some(dir) = &frames_dir {
+            info!("Wrote {} frames to {}", round, dir.display());
         }
 
         println!("Answer part 2: {}", removed);
@@ -103,3 +275,8 @@ pub fn main(args: &Args) {
         panic!("Invalid part: {}", args.part);
     }
 }
+
+#[test]
+fn test_examples() {
+    crate::days::check_example(4, "test");
+}
@@ -1,12 +1,214 @@
 use crate::args::Args;
 use crate::utils::input::read_input_lines;
+use crate::utils::rng::Rng;
+use crate::utils::validate::{Problem, numbered, parse};
+use itertools::Itertools;
 use range_collections::RangeSet2;
+use std::io::{BufRead, Write};
 use std::ops::Range;
 
-fn parse_range(s: &str) -> Range<i64> {
+/// IDs are `i64`, ranges are half-open over `i128` so one can end at `i64::MAX`.
+fn parse_range(s: &str) -> Range<i128> {
     let (start, end) = s.split_once('-').unwrap();
 
-    start.parse::<i64>().unwrap()..(end.parse::<i64>().unwrap() + 1i64)
+    i128::from(start.parse::<i64>().unwrap())..i128::from(end.parse::<i64>().unwrap()) + 1
+}
+
+fn build_interval_tree(ranges: &[Range<i128>]) -> RangeSet2<i128> {
+    ranges
+        .iter()
+        .fold(RangeSet2::<i128>::empty(), |mut acc, r| {
+            acc.union_with(&RangeSet2::from(r.clone()));
+            acc
+        })
+}
+
+fn range_set_size(range_set: &RangeSet2<i128>) -> i128 {
+    range_set
+        .boundaries()
+        .chunks(2)
+        .map(|c| c[1] - c[0])
+        .sum::<i128>()
+}
+
+/// Fresh ID ranges loaded once to answer many queries: the merged set answers
+/// "is it fresh" and "how many", the input ranges sorted by start answer "which ones".
+struct FreshIndex {
+    interval_tree: RangeSet2<i128>,
+    // (position in the input, range), sorted by range start
+    by_start: Vec<(usize, Range<i128>)>,
+}
+
+impl FreshIndex {
+    fn new(ranges: &[Range<i128>]) -> Self {
+        FreshIndex {
+            interval_tree: build_interval_tree(ranges),
+            by_start: ranges
+                .iter()
+                .cloned()
+                .enumerate()
+                .sorted_by_key(|(_, r)| r.start)
+                .collect(),
+        }
+    }
+
+    /// Input ranges overlapping `query`, in input order.
+    fn matching_ranges(&self, query: &Range<i128>) -> Vec<(usize, Range<i128>)> {
+        let candidates = self.by_start.partition_point(|(_, r)| r.start < query.end);
+        self.by_start[..candidates]
+            .iter()
+            .filter(|(_, r)| r.end > query.start)
+            .cloned()
+            .sorted_by_key(|(idx, _)| *idx)
+            .collect()
+    }
+
+    fn fresh_count(&self, query: &Range<i128>) -> i128 {
+        range_set_size(&(&self.interval_tree & &RangeSet2::from(query.clone())))
+    }
+
+    /// Answers a single `<id>` or `<a>-<b>` query line.
+    fn answer(&self, query: &str) -> String {
+        let query = query.trim();
+        let parsed = match query.split_once('-') {
+            Some((start, end)) => start
+                .trim()
+                .parse::<i64>()
+                .and_then(|start| Ok((start, end.trim().parse::<i64>()?))),
+            None => query.parse::<i64>().map(|id| (id, id)),
+        };
+        let Ok((start, last)) = parsed.map_err(|_| ()).and_then(|(start, last)| {
+            if start <= last {
+                Ok((start, last))
+            } else {
+                Err(())
+            }
+        }) else {
+            return format!(
+                "{}: invalid query, expected an ID or a range like 3-5",
+                query
+            );
+        };
+        let range = i128::from(start)..i128::from(last) + 1;
+
+        let matched = self.matching_ranges(&range);
+        let matched_str = if matched.is_empty() {
+            "no ranges matched".to_string()
+        } else {
+            format!(
+                "matched {}",
+                matched
+                    .iter()
+                    .map(|(idx, r)| format!("#{} ({}-{})", idx + 1, r.start, r.end - 1))
+                    .join(", ")
+            )
+        };
+        if range.end - range.start == 1 {
+            let freshness = if matched.is_empty() {
+                "spoiled"
+            } else {
+                "fresh"
+            };
+            format!("{}: {}, {}", query, freshness, matched_str)
+        } else {
+            format!(
+                "{}: {} of {} fresh, {}",
+                query,
+                self.fresh_count(&range),
+                range.end - range.start,
+                matched_str
+            )
+        }
+    }
+
+    fn serve(&self, input: impl BufRead, mut output: impl Write) -> std::io::Result<()> {
+        for line in input.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            writeln!(output, "{}", self.answer(&line))?;
+            output.flush()?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_fresh_index_queries() {
+    let ranges: Vec<_> = [
+        "3-5",
+        "10-14",
+        "16-20",
+        "12-18",
+        "9223372036854775800-9223372036854775807",
+    ]
+    .into_iter()
+    .map(parse_range)
+    .collect();
+    let index = FreshIndex::new(&ranges);
+    let mut output = Vec::new();
+    index
+        .serve(
+            "1\n5\n\n17\n9-12\n20-30\n5-3\nabc\n9223372036854775807\n9223372036854775806-9223372036854775807\n"
+                .as_bytes(),
+            &mut output,
+        )
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        "1: spoiled, no ranges matched\n\
+         5: fresh, matched #1 (3-5)\n\
+         17: fresh, matched #3 (16-20), #4 (12-18)\n\
+         9-12: 3 of 4 fresh, matched #2 (10-14), #4 (12-18)\n\
+         20-30: 1 of 11 fresh, matched #3 (16-20)\n\
+         5-3: invalid query, expected an ID or a range like 3-5\n\
+         abc: invalid query, expected an ID or a range like 3-5\n\
+         9223372036854775807: fresh, matched #5 (9223372036854775800-9223372036854775807)\n\
+         9223372036854775806-9223372036854775807: 2 of 2 fresh, \
+         matched #5 (9223372036854775800-9223372036854775807)\n"
+    );
+}
+
+/// Fresh `start-end` ranges with `start <= end`, then the available IDs, one per line.
+pub fn validate(lines: &[String]) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    let mut first_id = None;
+    for (line, row) in numbered(lines) {
+        if let Some((start, end)) = row.split_once('-') {
+            if let Some(id_line) = first_id {
+                problems.push(Problem::at(
+                    line,
+                    format!("Range after the available IDs started on line {}", id_line),
+                ));
+            }
+            let start = parse::<i64>(line, start, "an ID", &mut problems);
+            let end = parse::<i64>(line, end, "an ID", &mut problems);
+            if let (Some(start), Some(end)) = (start, end)
+                && start > end
+            {
+                problems.push(Problem::at(line, "Range ends before it starts"));
+            }
+        } else {
+            first_id.get_or_insert(line);
+            parse::<i64>(line, row, "an ID", &mut problems);
+        }
+    }
+    problems
+}
+
+#[test]
+fn test_validate() {
+    let lines = ["3-5", "9-4", "", "1", "x", "10-14"].map(String::from);
+    let problems = validate(&lines).iter().map(|p| p.to_string()).collect_vec();
+    assert_eq!(
+        problems,
+        [
+            "line 2: Range ends before it starts",
+            "line 5: 'x' is not an ID",
+            "line 6: Range after the available IDs started on line 4",
+        ]
+    );
 }
 
 pub fn main(args: &Args) {
@@ -17,34 +219,103 @@ pub fn main(args: &Args) {
         .map(|s| parse_range(s))
         .collect();
 
-    let ids: Vec<_> = lines
+    if args.serve {
+        FreshIndex::new(&ranges)
+            .serve(std::io::stdin().lock(), std::io::stdout().lock())
+            .expect("Unable to serve queries over stdin");
+        return;
+    }
+
+    match args.part {
+        1 | 2 => println!("{}", solve(args.part, &lines)),
+        _ => {
+            println!("Part {} is not yet implemented", args.part);
+        }
+    }
+}
+
+/// `size` fresh ranges, a blank line, then `size` IDs, half of them picked from inside a range.
+pub fn generate(rng: &mut Rng, size: usize) -> Vec<String> {
+    const MAX_ID: i64 = 1_000_000_000_000_000;
+    let ranges: Vec<(i64, i64)> = (0..size)
+        .map(|_| {
+            let start = rng.range(1..=MAX_ID);
+            (start, start + rng.range(0..=MAX_ID / 1000))
+        })
+        .collect();
+    let mut lines: Vec<String> = ranges
         .iter()
-        .filter(|s| !s.contains('-'))
-        .map(|s| s.parse::<i64>().unwrap())
+        .map(|(start, end)| format!("{}-{}", start, end))
         .collect();
+    lines.push(String::new());
+    for _ in 0..size {
+        let id = if rng.chance(0.5) {
+            let (start, end) = *rng.pick(&ranges);
+            rng.range(start..=end)
+        } else {
+            rng.range(1..=MAX_ID)
+        };
+        lines.push(id.to_string());
+    }
+    lines
+}
 
-    let interval_tree = ranges.iter().fold(RangeSet2::<i64>::empty(), |mut acc, r| {
-        acc.union_with(&RangeSet2::from(r.clone()));
-        acc
-    });
+pub fn solve(part: u8, lines: &[String]) -> String {
+    let ranges: Vec<_> = lines
+        .iter()
+        .filter(|s| s.contains('-'))
+        .map(|s| parse_range(s))
+        .collect();
+    let interval_tree = build_interval_tree(&ranges);
 
-    match args.part {
-        1 => {
-            let fresh_count = ids.iter().filter(|&id| interval_tree.contains(id)).count();
-            println!("{}", fresh_count);
-        }
-        2 => {
-            let size = interval_tree
-                .into_inner()
-                .as_slice()
-                .chunks(2)
-                .map(|c| c[1] - c[0])
-                .sum::<i64>();
-
-            println!("{}", size);
-        }
+    match part {
+        1 => lines
+            .iter()
+            .filter(|s| !s.contains('-'))
+            .map(|s| i128::from(s.parse::<i64>().unwrap()))
+            .filter(|id| interval_tree.contains(id))
+            .count()
+            .to_string(),
+        _ => range_set_size(&interval_tree).to_string(),
+    }
+}
+
+/// Reference answer without the range set: IDs are checked against every range in turn,
+/// and the fresh total comes from sweeping the ranges sorted by start.
+pub fn solve_naive(part: u8, lines: &[String]) -> String {
+    let ranges: Vec<(i128, i128)> = lines
+        .iter()
+        .filter_map(|s| s.split_once('-'))
+        .map(|(start, end)| {
+            let id = |s: &str| i128::from(s.parse::<i64>().unwrap());
+            (id(start), id(end))
+        })
+        .collect();
+
+    match part {
+        1 => lines
+            .iter()
+            .filter(|s| !s.contains('-'))
+            .map(|s| i128::from(s.parse::<i64>().unwrap()))
+            .filter(|&id| ranges.iter().any(|&(start, end)| start <= id && id <= end))
+            .count()
+            .to_string(),
         _ => {
-            println!("Part {} is not yet implemented", args.part);
+            let mut fresh = 0;
+            let mut covered_until = i128::MIN;
+            for (start, end) in ranges.into_iter().sorted() {
+                let start = start.max(covered_until + 1);
+                if start <= end {
+                    fresh += end - start + 1;
+                }
+                covered_until = covered_until.max(end);
+            }
+            fresh.to_string()
         }
     }
 }
+
+#[test]
+fn test_examples() {
+    crate::days::check_example(5, "test");
+}
@@ -1,39 +1,113 @@
 use crate::args::Args;
 use crate::utils::input::read_input_lines;
+use crate::utils::rng::Rng;
+use crate::utils::validate::{Problem, numbered, parse};
 use std::cmp::min;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Operator {
     Add,
+    Sub,
     Mul,
+    Div,
+    Rem,
+    Min,
+    Max,
+    Concat,
+    Pow,
+}
+
+/// Order in which a problem's operands are combined.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Fold {
+    /// `((a op b) op c)`
+    Left,
+    /// `(a op (b op c))`
+    Right,
 }
 
 impl Operator {
-    fn apply(&self, a: i64, b: i64) -> i64 {
+    /// Every supported operator, looked up by its worksheet symbol.
+    const ALL: [Operator; 9] = [
+        Operator::Add,
+        Operator::Sub,
+        Operator::Mul,
+        Operator::Div,
+        Operator::Rem,
+        Operator::Min,
+        Operator::Max,
+        Operator::Concat,
+        Operator::Pow,
+    ];
+
+    fn symbol(&self) -> &'static str {
         match self {
-            Operator::Add => a + b,
-            Operator::Mul => a * b,
+            Operator::Add => "+",
+            Operator::Sub => "-",
+            Operator::Mul => "*",
+            Operator::Div => "/",
+            Operator::Rem => "%",
+            Operator::Min => "min",
+            Operator::Max => "max",
+            Operator::Concat => "||",
+            Operator::Pow => "^",
         }
     }
 
-    fn identity(&self) -> i64 {
+    fn fold(&self) -> Fold {
         match self {
-            Operator::Add => 0,
-            Operator::Mul => 1,
+            Operator::Pow => Fold::Right,
+            _ => Fold::Left,
         }
     }
+
+    fn from_symbol(symbol: &str) -> Option<Operator> {
+        Operator::ALL.into_iter().find(|op| op.symbol() == symbol)
+    }
+
+    fn apply(&self, a: i64, b: i64) -> Result<i64, String> {
+        let result = match self {
+            Operator::Add => a.checked_add(b),
+            Operator::Sub => a.checked_sub(b),
+            Operator::Mul => a.checked_mul(b),
+            Operator::Div | Operator::Rem if b == 0 => {
+                return Err(format!("Division by zero in {} {} {}", a, self.symbol(), b));
+            }
+            Operator::Div => a.checked_div(b),
+            Operator::Rem => a.checked_rem(b),
+            Operator::Min => Some(a.min(b)),
+            Operator::Max => Some(a.max(b)),
+            Operator::Concat if b < 0 => {
+                return Err(format!("Can't concatenate negative {} to {}", b, a));
+            }
+            Operator::Concat => format!("{}{}", a, b).parse::<i64>().ok(),
+            Operator::Pow => u32::try_from(b)
+                .map_err(|_| format!("Negative exponent in {} ^ {}", a, b))
+                .map(|exp| a.checked_pow(exp))?,
+        };
+        result.ok_or_else(|| format!("Overflow in {} {} {}", a, self.symbol(), b))
+    }
 }
 
 impl From<&str> for Operator {
     fn from(c: &str) -> Self {
-        match c.trim() {
-            "+" => Operator::Add,
-            "*" => Operator::Mul,
-            _ => panic!("Invalid operator character: {}", c),
-        }
+        Operator::from_symbol(c.trim())
+            .unwrap_or_else(|| panic!("Invalid operator character: {}", c))
     }
 }
 
+#[test]
+fn test_operators() {
+    assert_eq!(Operator::from("min"), Operator::Min);
+    assert_eq!(Operator::Sub.apply(3, 5), Ok(-2));
+    assert_eq!(Operator::Rem.apply(17, 5), Ok(2));
+    assert_eq!(Operator::Concat.apply(12, 345), Ok(12345));
+    assert_eq!(Operator::Pow.apply(2, 10), Ok(1024));
+    assert!(Operator::Div.apply(1, 0).is_err());
+    assert!(Operator::Mul.apply(i64::MAX, 2).is_err());
+    assert!(Operator::Pow.apply(10, 19).is_err());
+}
+
 #[derive(Debug)]
 struct Operation {
     operands: Vec<i64>,
@@ -41,12 +115,19 @@ struct Operation {
 }
 
 impl Operation {
-    fn execute(&self) -> i64 {
-        self.operands
-            .iter()
-            .fold(self.operator.identity(), |acc, &op| {
-                self.operator.apply(acc, op)
-            })
+    fn execute(&self) -> Result<i64, String> {
+        let apply = |acc: Result<i64, String>, op: i64| match self.operator.fold() {
+            Fold::Left => self.operator.apply(acc?, op),
+            Fold::Right => self.operator.apply(op, acc?),
+        };
+        let mut operands = self.operands.iter().copied();
+        let result = match self.operator.fold() {
+            Fold::Left => operands.next().map(|first| operands.fold(Ok(first), apply)),
+            Fold::Right => operands
+                .next_back()
+                .map(|last| operands.rev().fold(Ok(last), apply)),
+        };
+        result.unwrap_or_else(|| Err(format!("No operands for {}", self.operator.symbol())))
     }
 
     // 123 328  51 64
@@ -58,6 +139,8 @@ impl Operation {
     // The second problem from the right is 175 * 581 * 32 = 3253600
     // The third problem from the right is 8 + 248 + 369 = 625
     // Finally, the leftmost problem is 356 * 24 * 1 = 8544
+    //
+    // Operands are read right-to-left, so `-` and `/` take the rightmost column first.
     fn from_part2(value: &[&str]) -> Self {
         let operator = Operator::from(value[value.len() - 1]);
         let max_len_operands: usize = value[..value.len() - 1]
@@ -71,16 +154,20 @@ impl Operation {
             .collect();
 
         let operands: Vec<_> = (0..padded_operands[0].len())
+            .rev()
             .map(|i| {
                 padded_operands
                     .iter()
                     .map(|s| s.chars().nth(i).unwrap())
                     .filter(|&s| s != ' ')
                     .collect::<String>()
-                    .parse::<i64>()
-                    .unwrap_or_else(|_| {
-                        panic!("Invalid operand at position {}: '{:?}'", i, value);
-                    })
+            })
+            // Columns are as wide as the operator, which may be wider than the numbers.
+            .filter(|digits| !digits.is_empty())
+            .map(|digits| {
+                digits.parse::<i64>().unwrap_or_else(|_| {
+                    panic!("Invalid operand '{}' in {:?}", digits, value);
+                })
             })
             .collect();
         Operation { operands, operator }
@@ -100,6 +187,35 @@ impl Operation {
     }
 }
 
+#[test]
+fn test_worksheet_with_extra_operators() {
+    let rows = vec![
+        "100 2 7   12 3",
+        " 20 3 30   3 4",
+        "  5 2 4    5 1",
+        "-   ^ min || %",
+    ];
+    let tokens = parse_tokens(rows);
+    let evaluate = |factory: fn(&[&str]) -> Operation| -> Vec<Result<i64, String>> {
+        (0..tokens[0].len())
+            .map(|i| {
+                let column: Vec<&str> = tokens.iter().map(|row| row[i].as_str()).collect();
+                factory(&column).execute()
+            })
+            .collect()
+    };
+    // 100 - 20 - 5, 2 ^ (3 ^ 2), min(7, 30, 4), 12 || 3 || 5, 3 % 4 % 1
+    assert_eq!(
+        evaluate(Operation::from_part1),
+        vec![Ok(75), Ok(512), Ok(4), Ok(1235), Ok(0)]
+    );
+    // 5 - 2 - 1, 232, min(0, 734), 235 || 1, 341
+    assert_eq!(
+        evaluate(Operation::from_part2),
+        vec![Ok(2), Ok(232), Ok(0), Ok(2351), Ok(341)]
+    );
+}
+
 fn parse_tokens(rows: Vec<&str>) -> Vec<Vec<String>> {
     let lenghts: Vec<Vec<usize>> = rows
         .iter()
@@ -131,18 +247,16 @@ fn parse_tokens(rows: Vec<&str>) -> Vec<Vec<String>> {
     tokens
 }
 
-pub fn main(args: &Args) {
-    let lines = read_input_lines(args.day as u32, args.input_tag.as_deref());
-
+fn evaluate_worksheet(part: u8, lines: &[String]) -> Result<i64, String> {
     let tokens = parse_tokens(lines.iter().map(|s| s.as_str()).collect::<Vec<&str>>());
 
     let mut raw_operation: Vec<&str> = vec![""; tokens.len()];
     let mut operations: Vec<Operation> = Vec::with_capacity(tokens[0].len());
 
-    let operation_factory = match args.part {
+    let operation_factory = match part {
         1 => |v: &[&str]| Operation::from_part1(v),
         2 => |v: &[&str]| Operation::from_part2(v),
-        _ => panic!("Part {} is not yet implemented", args.part),
+        _ => panic!("Part {} is not yet implemented", part),
     };
 
     #[allow(clippy::needless_range_loop)]
@@ -153,7 +267,181 @@ pub fn main(args: &Args) {
         operations.push(operation_factory(raw_operation.as_slice()));
     }
 
-    let result: i64 = operations.iter().map(|op| op.execute()).sum::<i64>();
+    operations
+        .iter()
+        .map(|op| op.execute())
+        .try_fold(0i64, |acc, value| {
+            acc.checked_add(value?)
+                .ok_or_else(|| "Overflow while summing the answers".to_string())
+        })
+}
+
+/// `size` problems of four numbers each, every problem aligned left or right in its column.
+pub fn generate(rng: &mut Rng, size: usize) -> Vec<String> {
+    const ROWS: usize = 4;
+    let mut lines = vec![String::new(); ROWS + 1];
+    for problem in 0..size {
+        let numbers: Vec<String> = (0..ROWS)
+            .map(|_| {
+                let digits = rng.range(1..=4) as u32;
+                rng.range(10i64.pow(digits - 1)..=10i64.pow(digits) - 1)
+                    .to_string()
+            })
+            .collect();
+        let width = numbers.iter().map(|n| n.len()).max().unwrap();
+        let align_left = rng.chance(0.5);
+        for (line, number) in lines.iter_mut().zip(&numbers) {
+            if problem > 0 {
+                line.push(' ');
+            }
+            if align_left {
+                line.push_str(&format!("{:<width$}", number));
+            } else {
+                line.push_str(&format!("{:>width$}", number));
+            }
+        }
+        let operator = if rng.chance(0.5) { '+' } else { '*' };
+        let operators = &mut lines[ROWS];
+        if problem > 0 {
+            operators.push(' ');
+        }
+        operators.push_str(&format!("{:<width$}", operator));
+    }
+    for line in &mut lines {
+        line.truncate(line.trim_end().len());
+    }
+    lines
+}
+
+pub fn solve(part: u8, lines: &[String]) -> String {
+    evaluate_worksheet(part, lines)
+        .unwrap_or_else(|e| panic!("Unable to evaluate the worksheet: {}", e))
+        .to_string()
+}
+
+/// Reference answer read straight off the character grid: problems are the column blocks
+/// between all-blank columns, evaluated with a plain loop.
+pub fn solve_naive(part: u8, lines: &[String]) -> String {
+    let width = lines.iter().map(|line| line.len()).max().unwrap_or(0);
+    let grid: Vec<Vec<u8>> = lines
+        .iter()
+        .map(|line| format!("{:width$}", line).into_bytes())
+        .collect();
+    let (operator_row, number_rows) = grid.split_last().unwrap();
+    let blank = |x: usize| grid.iter().all(|row| row[x] == b' ');
+
+    let mut total = 0i64;
+    let mut x = 0;
+    while x < width {
+        if blank(x) {
+            x += 1;
+            continue;
+        }
+        let block_start = x;
+        while x < width && !blank(x) {
+            x += 1;
+        }
+        let block = block_start..x;
+
+        let operator = Operator::from(std::str::from_utf8(&operator_row[block.clone()]).unwrap());
+        let operands: Vec<i64> = if part == 1 {
+            number_rows
+                .iter()
+                .map(|row| std::str::from_utf8(&row[block.clone()]).unwrap().trim())
+                .map(|number| number.parse().unwrap())
+                .collect()
+        } else {
+            block
+                .rev()
+                .map(|column| {
+                    number_rows
+                        .iter()
+                        .map(|row| row[column] as char)
+                        .filter(|c| !c.is_whitespace())
+                        .collect::<String>()
+                })
+                .filter(|digits| !digits.is_empty())
+                .map(|digits| digits.parse().unwrap())
+                .collect()
+        };
+
+        let mut value = if operator.fold() == Fold::Left {
+            operands[0]
+        } else {
+            operands[operands.len() - 1]
+        };
+        for i in 1..operands.len() {
+            value = match operator.fold() {
+                Fold::Left => operator.apply(value, operands[i]),
+                Fold::Right => operator.apply(operands[operands.len() - 1 - i], value),
+            }
+            .unwrap_or_else(|e| panic!("Unable to evaluate the worksheet: {}", e));
+        }
+        total += value;
+    }
+    total.to_string()
+}
+
+/// Rows of numbers over a row of operators, with the same number of problems in every row.
+pub fn validate(lines: &[String]) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    let rows = numbered(lines);
+    let Some((&(operator_line, operator_row), number_rows)) = rows.split_last() else {
+        problems.push(Problem::input("The worksheet is empty"));
+        return problems;
+    };
+    if number_rows.is_empty() {
+        problems.push(Problem::input("The worksheet has no numbers"));
+    }
+
+    let columns = operator_row.split_whitespace().count();
+    for symbol in operator_row.split_whitespace() {
+        if Operator::from_symbol(symbol).is_none() {
+            problems.push(Problem::at(
+                operator_line,
+                format!("'{}' is not an operator", symbol),
+            ));
+        }
+    }
+    for &(line, row) in number_rows {
+        let numbers: Vec<&str> = row.split_whitespace().collect();
+        if numbers.len() != columns {
+            problems.push(Problem::at(
+                line,
+                format!("{} numbers for {} operators", numbers.len(), columns),
+            ));
+        }
+        for number in numbers {
+            parse::<i64>(line, number, "a number", &mut problems);
+        }
+    }
+    problems
+}
+
+#[test]
+fn test_validate() {
+    let lines = ["123 328  51", " 45 6a  387 23", "*   ?   +"].map(String::from);
+    let problems: Vec<String> = validate(&lines).iter().map(|p| p.to_string()).collect();
+    assert_eq!(
+        problems,
+        [
+            "line 3: '?' is not an operator",
+            "line 2: 4 numbers for 3 operators",
+            "line 2: '6a' is not a number",
+        ]
+    );
+}
+
+pub fn main(args: &Args) {
+    let lines = read_input_lines(args.day as u32, args.input_tag.as_deref());
+
+    match evaluate_worksheet(args.part, &lines) {
+        Ok(result) => println!("Answer: {}", result),
+        Err(e) => println!("Unable to evaluate the worksheet: {}", e),
+    }
+}
 
-    println!("Answer: {}", result);
+#[test]
+fn test_examples() {
+    crate::days::check_example(6, "test");
 }
@@ -1,8 +1,14 @@
-use crate::args::Args;
+use crate::args::{Args, ExportFormat};
 use crate::utils::input::read_input_lines;
 use crate::utils::point::Point;
+use crate::utils::ppm::plain_ppm;
+use crate::utils::rng::Rng;
+use crate::utils::validate::{Problem, check_grid, numbered};
+use itertools::Itertools;
+use log::info;
 use smallvec::{SmallVec, smallvec};
 use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::PathBuf;
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 enum Cell {
@@ -64,6 +70,46 @@ impl Grid {
         }
     }
 
+    fn get_max_x(&self) -> usize {
+        self.cells[0].len()
+    }
+
+    /// Number of timelines that reach each beam cell from the start, row by row.
+    fn count_arrivals(&self) -> Vec<Vec<usize>> {
+        let mut arrivals = vec![vec![0usize; self.get_max_x()]; self.get_max_y()];
+        arrivals[self.start_pos.y][self.start_pos.x] = 1;
+        for y in 1..self.get_max_y() {
+            for x in 0..self.get_max_x() {
+                let count = arrivals[y - 1][x];
+                if count == 0 {
+                    continue;
+                }
+                if self.is_splitter(Point { x, y }) {
+                    arrivals[y][x - 1] += count;
+                    arrivals[y][x + 1] += count;
+                } else {
+                    arrivals[y][x] += count;
+                }
+            }
+        }
+        arrivals
+    }
+
+    /// Number of timelines passing through each cell: the ways to get there from the
+    /// start times the ways to continue from there, shared with the part 2 DP.
+    fn count_timelines_through(&self) -> Vec<Vec<usize>> {
+        let mut cache: HashMap<Point, usize> = HashMap::new();
+        let mut through = self.count_arrivals();
+        for (y, row) in through.iter_mut().enumerate() {
+            for (x, count) in row.iter_mut().enumerate() {
+                if *count > 0 {
+                    *count *= self.count_trajectories_impl(Point { x, y }, &mut cache);
+                }
+            }
+        }
+        through
+    }
+
     fn count_trajectories(&self, beam: Point) -> usize {
         let mut cache: HashMap<Point, usize> = HashMap::new();
         self.count_trajectories_impl(beam, &mut cache)
@@ -108,6 +154,83 @@ impl Grid {
     }
 }
 
+impl Cell {
+    fn symbol(&self) -> char {
+        match self {
+            Cell::Start => 'S',
+            Cell::Splitter => '^',
+            Cell::Empty => '.',
+        }
+    }
+}
+
+/// The manifold with `|` on every cell a beam passes through.
+fn render_beams(grid: &Grid, beams_per_row: &[Vec<Point>]) -> String {
+    let mut rows: Vec<Vec<char>> = grid
+        .cells
+        .iter()
+        .map(|row| row.iter().map(Cell::symbol).collect())
+        .collect();
+    for p in beams_per_row.iter().flatten() {
+        if grid.cells[p.y][p.x] == Cell::Empty {
+            rows[p.y][p.x] = '|';
+        }
+    }
+    rows.into_iter().map(String::from_iter).join("\n")
+}
+
+/// How hot a cell is on a log scale, from 0.0 (a single timeline) to 1.0 (the busiest).
+fn heat(count: usize, max_count: usize) -> f64 {
+    if max_count <= 1 {
+        1.0
+    } else {
+        (count as f64).ln() / (max_count as f64).ln()
+    }
+}
+
+/// Timelines through each cell as characters, from ` ` (none) to `@` (the most).
+fn render_heatmap(grid: &Grid, through: &[Vec<usize>]) -> String {
+    const LEVELS: &[u8] = b".:-=+*#%@";
+    let max_count = through.iter().flatten().copied().max().unwrap_or(0);
+    through
+        .iter()
+        .enumerate()
+        .map(|(y, row)| {
+            row.iter()
+                .enumerate()
+                .map(|(x, &count)| match grid.cells[y][x] {
+                    Cell::Empty if count == 0 => ' ',
+                    Cell::Empty => {
+                        let level = heat(count, max_count) * (LEVELS.len() - 1) as f64;
+                        LEVELS[level.round() as usize] as char
+                    }
+                    cell => cell.symbol(),
+                })
+                .collect::<String>()
+        })
+        .join("\n")
+}
+
+/// Timelines through each cell as a plain PPM image: splitters are black, cells no beam
+/// reaches are white, and beams go from blue (few timelines) to orange (many).
+fn heatmap_ppm(grid: &Grid, through: &[Vec<usize>]) -> String {
+    let max_count = through.iter().flatten().copied().max().unwrap_or(0);
+    plain_ppm(grid.get_max_x(), grid.get_max_y(), 1, |x, y| {
+        match grid.cells[y][x] {
+            Cell::Splitter => (0, 0, 0),
+            _ if through[y][x] == 0 => (255, 255, 255),
+            _ => {
+                let t = heat(through[y][x], max_count);
+                (
+                    (255.0 * t) as u8,
+                    (64.0 + 96.0 * t) as u8,
+                    (255.0 * (1.0 - t)) as u8,
+                )
+            }
+        }
+    })
+}
+
 impl From<u8> for Cell {
     fn from(value: u8) -> Self {
         match value {
@@ -144,26 +267,199 @@ impl From<Vec<String>> for Grid {
     }
 }
 
+#[test]
+fn test_timelines_through() {
+    let grid = Grid::from(read_input_lines(7, Some("test")));
+    let through = grid.count_timelines_through();
+    let total = grid.count_trajectories(grid.start_pos);
+    assert_eq!(total, 40);
+    assert_eq!(through[0][grid.start_pos.x], total);
+    assert_eq!(through[grid.get_max_y() - 1].iter().sum::<usize>(), total);
+    assert_eq!(through[2][6] + through[2][8], total);
+}
+
+#[test]
+fn test_render_beams() {
+    let lines: Vec<String> = ["..S..", ".....", "..^..", "....."]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+    let grid = Grid::from(lines);
+    let beams = vec![
+        vec![grid.start_pos],
+        vec![Point { x: 2, y: 1 }],
+        vec![Point { x: 1, y: 2 }, Point { x: 3, y: 2 }],
+        vec![Point { x: 1, y: 3 }, Point { x: 3, y: 3 }],
+    ];
+    assert_eq!(render_beams(&grid, &beams), "..S..\n..|..\n.|^|.\n.|.|.");
+    assert_eq!(
+        render_heatmap(&grid, &grid.count_timelines_through()),
+        "  S  \n  @  \n .^. \n . . "
+    );
+}
+
+/// Beam positions on every row from the start down, and the number of splits on the way.
+fn trace_beams(grid: &Grid) -> (Vec<Vec<Point>>, usize) {
+    let mut current_y = 1;
+    let mut beams_per_row = vec![vec![grid.start_pos]];
+    let mut splits = 0;
+    while current_y < grid.get_max_y() {
+        let beam_result = grid.project_beams(beams_per_row.last().unwrap(), current_y);
+        beams_per_row.push(beam_result.beams);
+        splits += beam_result.splits;
+        current_y += 1;
+    }
+    (beams_per_row, splits)
+}
+
+/// A manifold with `size` splitter rows below `S`, splitters only where a beam can arrive.
+/// Taller manifolds get sparser splitters so the timeline count still fits in `usize`.
+pub fn generate(rng: &mut Rng, size: usize) -> Vec<String> {
+    let density = (30.0 / size as f64).min(0.6);
+    let width = 2 * size + 3;
+    let start = size + 1;
+    let mut lines = Vec::with_capacity(2 * size + 2);
+    lines.push(
+        (0..width)
+            .map(|x| if x == start { 'S' } else { '.' })
+            .collect(),
+    );
+    for row in 1..=size {
+        lines.push(".".repeat(width));
+        lines.push(
+            (0..width)
+                .map(|x| {
+                    let offset = x.abs_diff(start);
+                    let reachable = offset < row && offset % 2 == (row - 1) % 2;
+                    if reachable && rng.chance(density) {
+                        '^'
+                    } else {
+                        '.'
+                    }
+                })
+                .collect(),
+        );
+    }
+    lines.push(".".repeat(width));
+    lines
+}
+
+pub fn solve(part: u8, lines: &[String]) -> String {
+    let grid = Grid::from(lines.to_vec());
+    match part {
+        1 => trace_beams(&grid).1,
+        _ => grid.count_trajectories(grid.start_pos),
+    }
+    .to_string()
+}
+
+/// Reference answer on the raw characters: part 1 moves a set of beam columns down row by
+/// row, part 2 follows every timeline separately without any caching.
+pub fn solve_naive(part: u8, lines: &[String]) -> String {
+    let rows: Vec<&[u8]> = lines.iter().map(|line| line.as_bytes()).collect();
+    let start = rows[0].iter().position(|&c| c == b'S').unwrap();
+
+    if part == 1 {
+        let mut beams: BTreeSet<usize> = BTreeSet::from([start]);
+        let mut splits = 0;
+        for row in &rows[1..] {
+            let mut next = BTreeSet::new();
+            for &x in &beams {
+                if row[x] == b'^' {
+                    splits += 1;
+                    next.insert(x - 1);
+                    next.insert(x + 1);
+                } else {
+                    next.insert(x);
+                }
+            }
+            beams = next;
+        }
+        return splits.to_string();
+    }
+
+    fn timelines(rows: &[&[u8]], x: usize, y: usize) -> usize {
+        if y + 1 == rows.len() {
+            1
+        } else if rows[y + 1][x] == b'^' {
+            timelines(rows, x - 1, y + 1) + timelines(rows, x + 1, y + 1)
+        } else {
+            timelines(rows, x, y + 1)
+        }
+    }
+    timelines(&rows, start, 0).to_string()
+}
+
+/// A rectangle of `.`, `^` and a single `S`, which has to be in the top row.
+pub fn validate(lines: &[String]) -> Vec<Problem> {
+    let rows = numbered(lines);
+    let mut problems = check_grid(&rows, "S^.");
+    let starts: Vec<usize> = rows
+        .iter()
+        .flat_map(|&(line, row)| row.matches('S').map(move |_| line))
+        .collect();
+    match rows.first() {
+        Some(&(top, row)) if !row.contains('S') => {
+            problems.push(Problem::at(top, "The top row has no start 'S'"))
+        }
+        _ => {}
+    }
+    for (i, &line) in starts.iter().enumerate() {
+        if i > 0 || Some(line) != rows.first().map(|&(top, _)| top) {
+            problems.push(Problem::at(
+                line,
+                "Extra start 'S'; the only one belongs in the top row",
+            ));
+        }
+    }
+    problems
+}
+
+#[test]
+fn test_validate() {
+    let lines = ["...", ".^S", ".^.."].map(String::from);
+    let problems = validate(&lines).iter().map(|p| p.to_string()).collect_vec();
+    assert_eq!(
+        problems,
+        [
+            "line 3: Row is 4 cells wide, the first row is 3",
+            "line 1: The top row has no start 'S'",
+            "line 2: Extra start 'S'; the only one belongs in the top row",
+        ]
+    );
+}
+
 pub fn main(args: &Args) {
     let lines = read_input_lines(args.day as u32, args.input_tag.as_deref());
     let grid = Grid::from(lines);
 
     match args.part {
         1 => {
-            let mut current_y = 1;
-            let mut beams = vec![grid.start_pos];
-            let mut splits = 0;
-            while current_y < grid.get_max_y() {
-                let beam_result = grid.project_beams(&beams, current_y);
-                // println!("Beams at y={} : {:?}, splits={}", current_y, beam_result.beams, beam_result.splits);
-                beams = beam_result.beams;
-                splits += beam_result.splits;
-                current_y += 1;
+            let (beams_per_row, splits) = trace_beams(&grid);
+
+            if args.render {
+                println!("{}", render_beams(&grid, &beams_per_row));
             }
 
             println!("Solution: {}", splits);
         }
         2 => {
+            if args.heatmap || args.export == Some(ExportFormat::Ppm) {
+                let through = grid.count_timelines_through();
+                if args.heatmap {
+                    println!("{}", render_heatmap(&grid, &through));
+                }
+                if args.export == Some(ExportFormat::Ppm) {
+                    let path = args
+                        .output
+                        .clone()
+                        .unwrap_or_else(|| PathBuf::from("day07.ppm"));
+                    std::fs::write(&path, heatmap_ppm(&grid, &through))
+                        .unwrap_or_else(|e| panic!("Unable to write {}: {e}", path.display()));
+                    info!("Wrote the timelines heat map to {}", path.display());
+                }
+            }
+
             let total_trajectories = grid.count_trajectories(grid.start_pos);
             println!("Solution: {}", total_trajectories);
         }
@@ -172,3 +468,8 @@ pub fn main(args: &Args) {
         }
     }
 }
+
+#[test]
+fn test_examples() {
+    crate::days::check_example(7, "test");
+}
@@ -1,6 +1,10 @@
-use crate::args::Args;
+use crate::args::{Args, ExportFormat};
 use crate::utils::input::read_input_lines;
+use crate::utils::rng::Rng;
+use crate::utils::validate::{Problem, numbered, parse};
 use itertools::Itertools;
+use std::fs;
+use std::path::PathBuf;
 
 #[derive(Debug, Clone, Copy)]
 struct Vector {
@@ -31,9 +35,6 @@ impl Vector {
     }
 }
 
-type BreakMergeFn<'a> =
-    Box<dyn Fn(usize, Option<&Vec<usize>>, &'a VectorPair) -> (bool, &'a VectorPair) + 'a>;
-
 impl Vector {
     fn from_string_and_index(s: &str, index: usize) -> Self {
         let coords: Vec<i64> = s
@@ -50,15 +51,16 @@ impl Vector {
     }
 }
 
-pub fn main(args: &Args) {
-    let lines = read_input_lines(args.day as u32, args.input_tag.as_deref());
-    let vectors: Vec<_> = lines
-        .into_iter()
+fn read_vectors(lines: &[String]) -> Vec<Vector> {
+    lines
+        .iter()
         .enumerate()
-        .map(|(usize, line)| Vector::from_string_and_index(line.as_str(), usize))
-        .collect();
+        .map(|(index, line)| Vector::from_string_and_index(line.as_str(), index))
+        .collect()
+}
 
-    let pairs_to_merge: Vec<_> = vectors
+fn sorted_pairs(vectors: &[Vector]) -> Vec<VectorPair> {
+    vectors
         .iter()
         .combinations(2)
         .map(|vectors| VectorPair {
@@ -70,79 +72,436 @@ pub fn main(args: &Args) {
             let d2 = p2.distance();
             f64::partial_cmp(&d1, &d2).unwrap()
         })
-        .collect();
+        .collect()
+}
 
-    let mut cluster_to_vectors: Vec<Vec<usize>> = vectors
-        .iter()
-        .enumerate()
-        .map(|(i, _vec)| vec![i])
-        .collect();
-    let mut vector_to_cluster: Vec<usize> = vectors.iter().enumerate().map(|(i, _vec)| i).collect();
+/// Clusters of junction boxes, each named after the smallest cluster index it absorbed.
+struct Clusters {
+    cluster_to_vectors: Vec<Vec<usize>>,
+    vector_to_cluster: Vec<usize>,
+}
 
-    let break_merge_when: BreakMergeFn = match &args.part {
-        1 => {
-            let num_pairs_to_merge = args
-                .input_tag
-                .as_deref()
-                .map(|_tag| 10usize)
-                .unwrap_or(vectors.len());
-            println!("Part 1: Number of pairs to merge: {}", num_pairs_to_merge);
-            Box::new(move |index, _, vp| (index + 1 == num_pairs_to_merge, vp))
+impl Clusters {
+    fn new(vector_count: usize) -> Self {
+        Clusters {
+            cluster_to_vectors: (0..vector_count).map(|i| vec![i]).collect(),
+            vector_to_cluster: (0..vector_count).collect(),
         }
-        2 => Box::new(|_, cluster, vp| {
-            (
-                cluster.map(|c| c.len() == vectors.len()).unwrap_or(false),
-                vp,
-            )
-        }),
-        _ => panic!("Unsupported part for day {}", args.day),
-    };
+    }
 
-    let mut last_vector_pair: Option<&VectorPair> = None;
-    for (idx, pair) in pairs_to_merge.iter().enumerate() {
-        let cluster1 = vector_to_cluster[pair.v1.index];
-        let cluster2 = vector_to_cluster[pair.v2.index];
-        let break_cond = if cluster1 != cluster2 {
-            let min_cluster = usize::min(cluster1, cluster2);
-            let max_cluster = usize::max(cluster1, cluster2);
-
-            let slices = cluster_to_vectors.split_at_mut(max_cluster);
-            let min_cluster_size_before = slices.0[min_cluster].len();
-            slices.0[min_cluster].append(&mut slices.1[0]);
-            for &vec_index in &slices.0[min_cluster][min_cluster_size_before..] {
-                vector_to_cluster[vec_index] = min_cluster;
-            }
+    /// Connects both ends of the pair. Returns the surviving and the absorbed cluster,
+    /// or `None` when the ends were already in the same cluster.
+    fn join(&mut self, pair: &VectorPair) -> Option<(usize, usize)> {
+        let cluster1 = self.vector_to_cluster[pair.v1.index];
+        let cluster2 = self.vector_to_cluster[pair.v2.index];
+        if cluster1 == cluster2 {
+            return None;
+        }
+        let min_cluster = usize::min(cluster1, cluster2);
+        let max_cluster = usize::max(cluster1, cluster2);
 
-            break_merge_when(idx, Some(&slices.0[min_cluster]), pair)
-        } else {
-            break_merge_when(idx, None, pair)
-        };
+        let slices = self.cluster_to_vectors.split_at_mut(max_cluster);
+        let min_cluster_size_before = slices.0[min_cluster].len();
+        slices.0[min_cluster].append(&mut slices.1[0]);
+        for &vec_index in &slices.0[min_cluster][min_cluster_size_before..] {
+            self.vector_to_cluster[vec_index] = min_cluster;
+        }
+        Some((min_cluster, max_cluster))
+    }
 
-        if break_cond.0 {
-            last_vector_pair = Some(break_cond.1);
+    fn non_empty(&self) -> impl Iterator<Item = &Vec<usize>> {
+        self.cluster_to_vectors
+            .iter()
+            .filter(|vecs| !vecs.is_empty())
+    }
+}
+
+/// Clusters after connecting the closest pairs for as long as `connect` allows.
+fn clusters_after(
+    vector_count: usize,
+    pairs: &[VectorPair],
+    connect: impl Fn(usize, &VectorPair) -> bool,
+) -> Clusters {
+    let mut clusters = Clusters::new(vector_count);
+    for (idx, pair) in pairs.iter().enumerate() {
+        if !connect(idx, pair) {
             break;
         }
+        clusters.join(pair);
     }
+    clusters
+}
 
-    match &args.part {
-        1 => {
-            let cluster_sizes = cluster_to_vectors
-                .into_iter()
-                .filter(|vecs| !vecs.is_empty())
-                .map(|vecs| vecs.len())
-                .k_largest_by_key(3, |&size| size)
-                .collect::<Vec<usize>>();
+/// Every cluster, largest first, with its bounding box and member coordinates.
+fn cluster_report(vectors: &[Vector], clusters: &Clusters) -> String {
+    let mut report = String::new();
+    let sorted = clusters
+        .non_empty()
+        .sorted_by_key(|members| (std::cmp::Reverse(members.len()), members.iter().min()));
+    for (i, members) in sorted.enumerate() {
+        let boxes: Vec<&Vector> = members.iter().sorted().map(|&m| &vectors[m]).collect();
+        let (min_x, max_x) = boxes.iter().map(|v| v.x).minmax().into_option().unwrap();
+        let (min_y, max_y) = boxes.iter().map(|v| v.y).minmax().into_option().unwrap();
+        let (min_z, max_z) = boxes.iter().map(|v| v.z).minmax().into_option().unwrap();
+        report.push_str(&format!(
+            "Cluster {} ({} boxes): x {}..{}, y {}..{}, z {}..{}\n",
+            i + 1,
+            boxes.len(),
+            min_x,
+            max_x,
+            min_y,
+            max_y,
+            min_z,
+            max_z
+        ));
+        for v in boxes {
+            report.push_str(&format!("  #{}: {},{},{}\n", v.index, v.x, v.y, v.z));
+        }
+    }
+    report
+}
+
+/// One step of single-linkage clustering: the pair that joined two clusters.
+#[derive(Debug)]
+struct MergeStep {
+    pair_index: usize,
+    box1: usize,
+    box2: usize,
+    cluster: usize,
+    absorbed: usize,
+    distance: f64,
+    size: usize,
+}
+
+/// Merge steps in order until every box is in one cluster.
+fn merge_history(vector_count: usize, pairs: &[VectorPair]) -> Vec<MergeStep> {
+    let mut clusters = Clusters::new(vector_count);
+    let mut history = Vec::new();
+    for (pair_index, pair) in pairs.iter().enumerate() {
+        if history.len() + 1 >= vector_count {
+            break;
+        }
+        if let Some((cluster, absorbed)) = clusters.join(pair) {
+            history.push(MergeStep {
+                pair_index,
+                box1: pair.v1.index,
+                box2: pair.v2.index,
+                cluster,
+                absorbed,
+                distance: pair.distance(),
+                size: clusters.cluster_to_vectors[cluster].len(),
+            });
+        }
+    }
+    history
+}
+
+fn history_to_csv(history: &[MergeStep]) -> String {
+    let mut csv = String::from("step,pair_index,box1,box2,cluster,absorbed,distance,size\n");
+    for (step, merge) in history.iter().enumerate() {
+        csv.push_str(&format!(
+            "{},{},{},{},{},{},{:.6},{}\n",
+            step + 1,
+            merge.pair_index,
+            merge.box1,
+            merge.box2,
+            merge.cluster,
+            merge.absorbed,
+            merge.distance,
+            merge.size
+        ));
+    }
+    csv
+}
+
+/// Dendrogram with boxes as leaves named by line index and merge distances as heights.
+fn history_to_newick(vector_count: usize, history: &[MergeStep]) -> String {
+    let mut subtrees: Vec<Option<(String, f64)>> = (0..vector_count)
+        .map(|i| Some((i.to_string(), 0.0)))
+        .collect();
+    for merge in history {
+        let (left, left_height) = subtrees[merge.cluster].take().unwrap();
+        let (right, right_height) = subtrees[merge.absorbed].take().unwrap();
+        subtrees[merge.cluster] = Some((
+            format!(
+                "({}:{:.6},{}:{:.6})",
+                left,
+                merge.distance - left_height,
+                right,
+                merge.distance - right_height
+            ),
+            merge.distance,
+        ));
+    }
+    // An incomplete history leaves a forest, which hangs off a common root.
+    let roots: Vec<String> = subtrees
+        .into_iter()
+        .flatten()
+        .map(|(tree, _)| tree)
+        .collect();
+    match roots.as_slice() {
+        [root] => format!("{};", root),
+        _ => format!("({});", roots.join(",")),
+    }
+}
+
+/// Every junction box is a line of three comma-separated coordinates.
+pub fn validate(lines: &[String]) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    let boxes = numbered(lines);
+    if boxes.len() < 2 {
+        problems.push(Problem::input(
+            "There should be at least two junction boxes",
+        ));
+    }
+    for (line, junction_box) in boxes {
+        let coords: Vec<&str> = junction_box.split(',').collect();
+        if coords.len() != 3 {
+            problems.push(Problem::at(
+                line,
+                format!("{} coordinates instead of 3", coords.len()),
+            ));
+        }
+        for coord in coords {
+            parse::<i64>(line, coord, "a coordinate", &mut problems);
+        }
+    }
+    problems
+}
 
+#[test]
+fn test_validate() {
+    let lines = ["162,817,812", "57,618", "906,x,560"].map(String::from);
+    let problems = validate(&lines).iter().map(|p| p.to_string()).collect_vec();
+    assert_eq!(
+        problems,
+        [
+            "line 2: 2 coordinates instead of 3",
+            "line 3: 'x' is not a coordinate",
+        ]
+    );
+}
+
+pub fn main(args: &Args) {
+    let lines = read_input_lines(args.day as u32, args.input_tag.as_deref());
+    let vectors = read_vectors(&lines);
+    let pairs_to_merge = sorted_pairs(&vectors);
+
+    if args.merges.is_some() || args.threshold.is_some() {
+        let merges = args.merges.unwrap_or(usize::MAX);
+        let threshold = args.threshold.unwrap_or(f64::INFINITY);
+        let clusters = clusters_after(vectors.len(), &pairs_to_merge, |idx, pair| {
+            idx < merges && pair.distance() <= threshold
+        });
+        print!("{}", cluster_report(&vectors, &clusters));
+    }
+
+    match args.export {
+        Some(format @ (ExportFormat::Newick | ExportFormat::Csv)) => {
+            let history = merge_history(vectors.len(), &pairs_to_merge);
+            let (contents, default_path) = if format == ExportFormat::Newick {
+                (history_to_newick(vectors.len(), &history), "day08.nwk")
+            } else {
+                (history_to_csv(&history), "day08.csv")
+            };
+            let path = args
+                .output
+                .clone()
+                .unwrap_or_else(|| PathBuf::from(default_path));
+            fs::write(&path, contents)
+                .unwrap_or_else(|e| panic!("Unable to write {}: {e}", path.display()));
+            println!("Wrote {} merge steps to {}", history.len(), path.display());
+        }
+        Some(format) => panic!("Day 8 cannot export {:?}", format),
+        None => {}
+    }
+
+    match args.part {
+        1 => {
+            // Only the puzzle's example connects fewer pairs; every other input, generated
+            // or saved by `diff` included, connects one per box like `solve` does.
+            let num_pairs_to_merge = if args.input_tag.as_deref() == Some("test") {
+                EXAMPLE_PAIRS
+            } else {
+                vectors.len()
+            };
+            println!("Part 1: Number of pairs to merge: {}", num_pairs_to_merge);
             println!(
                 "{:?}",
-                cluster_sizes[0] * cluster_sizes[1] * cluster_sizes[2]
+                largest_clusters_product(vectors.len(), &pairs_to_merge, num_pairs_to_merge)
             );
         }
         2 => {
-            let v1 = last_vector_pair.unwrap().v1;
-            let v2 = last_vector_pair.unwrap().v2;
-            println!("{}", v1.x * v2.x)
+            let history = merge_history(vectors.len(), &pairs_to_merge);
+            let last = history.last().unwrap();
+            println!("{}", vectors[last.box1].x * vectors[last.box2].x)
         }
         _ => panic!("Unsupported part for day {}", args.day),
     }
 }
+
+/// Closest pairs the examples connect in part 1; the real input connects one per box.
+const EXAMPLE_PAIRS: usize = 10;
+
+fn largest_clusters_product(vector_count: usize, pairs: &[VectorPair], connect: usize) -> usize {
+    let clusters = clusters_after(vector_count, pairs, |idx, _| idx < connect);
+    clusters
+        .non_empty()
+        .map(|vecs| vecs.len())
+        .k_largest_by_key(3, |&size| size)
+        .product()
+}
+
+/// `size` junction boxes with coordinates below 100k.
+pub fn generate(rng: &mut Rng, size: usize) -> Vec<String> {
+    (0..size)
+        .map(|_| {
+            format!(
+                "{},{},{}",
+                rng.range(0..=99_999),
+                rng.range(0..=99_999),
+                rng.range(0..=99_999)
+            )
+        })
+        .collect()
+}
+
+/// Part 1 connects one pair per junction box, as on the real input.
+pub fn solve(part: u8, lines: &[String]) -> String {
+    solve_connecting(part, lines, lines.len())
+}
+
+/// Same as [`solve`] with part 1 connecting the `connect` closest pairs.
+fn solve_connecting(part: u8, lines: &[String], connect: usize) -> String {
+    let vectors = read_vectors(lines);
+    let pairs = sorted_pairs(&vectors);
+    match part {
+        1 => largest_clusters_product(vectors.len(), &pairs, connect).to_string(),
+        _ => {
+            let last = merge_history(vectors.len(), &pairs).pop().unwrap();
+            (vectors[last.box1].x * vectors[last.box2].x).to_string()
+        }
+    }
+}
+
+/// Reference answer from graph searches: part 1 counts connected components of the closest
+/// pairs with a DFS, part 2 takes the longest edge of a minimum spanning tree built by Prim.
+pub fn solve_naive(part: u8, lines: &[String]) -> String {
+    solve_naive_connecting(part, lines, lines.len())
+}
+
+fn solve_naive_connecting(part: u8, lines: &[String], connect: usize) -> String {
+    let boxes: Vec<[i64; 3]> = lines
+        .iter()
+        .map(|line| {
+            let coords: Vec<i64> = line.split(',').map(|c| c.trim().parse().unwrap()).collect();
+            [coords[0], coords[1], coords[2]]
+        })
+        .collect();
+    let squared = |a: usize, b: usize| -> i64 {
+        (0..3)
+            .map(|axis| (boxes[a][axis] - boxes[b][axis]).pow(2))
+            .sum()
+    };
+    let n = boxes.len();
+
+    if part == 1 {
+        let mut pairs: Vec<(usize, usize)> = (0..n)
+            .flat_map(|a| (a + 1..n).map(move |b| (a, b)))
+            .collect();
+        pairs.sort_by_key(|&(a, b)| squared(a, b));
+        let mut neighbours = vec![Vec::new(); n];
+        for &(a, b) in pairs.iter().take(connect) {
+            neighbours[a].push(b);
+            neighbours[b].push(a);
+        }
+
+        let mut seen = vec![false; n];
+        let mut sizes = Vec::new();
+        for root in 0..n {
+            if seen[root] {
+                continue;
+            }
+            seen[root] = true;
+            let mut stack = vec![root];
+            let mut size = 0;
+            while let Some(node) = stack.pop() {
+                size += 1;
+                for &next in &neighbours[node] {
+                    if !seen[next] {
+                        seen[next] = true;
+                        stack.push(next);
+                    }
+                }
+            }
+            sizes.push(size);
+        }
+        sizes.sort_unstable_by(|a, b| b.cmp(a));
+        return sizes.iter().take(3).product::<usize>().to_string();
+    }
+
+    // The last merge joins the two halves of the minimum spanning tree's longest edge.
+    let mut in_tree = vec![false; n];
+    let mut best: Vec<(i64, usize)> = vec![(i64::MAX, 0); n];
+    best[0] = (0, 0);
+    let mut longest = (0i64, 0usize, 0usize);
+    for _ in 0..n {
+        let next = (0..n)
+            .filter(|&v| !in_tree[v])
+            .min_by_key(|&v| best[v].0)
+            .unwrap();
+        in_tree[next] = true;
+        let (distance, from) = best[next];
+        if distance > longest.0 {
+            longest = (distance, from, next);
+        }
+        for v in 0..n {
+            if !in_tree[v] && squared(next, v) < best[v].0 {
+                best[v] = (squared(next, v), next);
+            }
+        }
+    }
+    (boxes[longest.1][0] * boxes[longest.2][0]).to_string()
+}
+
+#[test]
+fn test_cluster_report_and_history() {
+    let lines = read_input_lines(8, Some("test"));
+    let vectors = read_vectors(&lines);
+    let pairs = sorted_pairs(&vectors);
+
+    let clusters = clusters_after(vectors.len(), &pairs, |idx, _| idx < 10);
+    let sizes: Vec<usize> = clusters
+        .non_empty()
+        .map(|c| c.len())
+        .sorted()
+        .rev()
+        .collect();
+    assert_eq!(&sizes[..4], &[5, 4, 2, 2]);
+    let report = cluster_report(&vectors, &clusters);
+    assert!(report.starts_with("Cluster 1 (5 boxes): "));
+    assert_eq!(report.lines().count(), sizes.len() + vectors.len());
+
+    let history = merge_history(vectors.len(), &pairs);
+    assert_eq!(history.len(), vectors.len() - 1);
+    let last = history.last().unwrap();
+    assert_eq!(last.size, vectors.len());
+    assert_eq!(vectors[last.box1].x * vectors[last.box2].x, 25272);
+    assert!(history.windows(2).all(|w| w[0].distance <= w[1].distance));
+
+    let newick = history_to_newick(vectors.len(), &history);
+    assert!(newick.ends_with(");"));
+    assert_eq!(newick.matches('(').count(), vectors.len() - 1);
+    assert_eq!(history_to_csv(&history).lines().count(), vectors.len());
+}
+
+#[test]
+fn test_examples() {
+    let lines = read_input_lines(8, Some("test"));
+    let expected = crate::utils::input::read_expected_answers(8, Some("test"));
+    assert!(!expected.is_empty());
+    for (part, answer) in expected {
+        assert_eq!(solve_connecting(part, &lines, EXAMPLE_PAIRS), answer);
+        assert_eq!(solve_naive_connecting(part, &lines, EXAMPLE_PAIRS), answer);
+    }
+}
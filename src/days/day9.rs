@@ -145,17 +145,15 @@
 // Using two red tiles as opposite corners, what is the largest area of any rectangle you can make using only red and green tiles?
 
 use crate::args::Args;
+use crate::utils::cancel::checkpoint;
 use crate::utils::input::read_input_lines;
 use crate::utils::point::Point;
+use crate::utils::rng::Rng;
+use crate::utils::svg::Svg;
+use crate::utils::validate::{Problem, numbered, parse};
 use itertools::Itertools;
 use log::info;
-use range_collections::RangeSet2;
-use roaring::RoaringTreemap;
-use smallvec::SmallVec;
-use std::collections::VecDeque;
-use std::fs::File;
-
-const MOD: usize = 100_000;
+use std::collections::{HashMap, VecDeque};
 
 fn get_area(p1: &Point, p2: &Point) -> i64 {
     let width = (p1.x as i64 - p2.x as i64).abs() + 1;
@@ -163,75 +161,233 @@ fn get_area(p1: &Point, p2: &Point) -> i64 {
     width * height
 }
 
-pub fn main_part1(points: Vec<Point>) {
+fn find_max_rectangle(points: &[Point]) -> (Point, Point, i64) {
     let max_rectangle = points
         .iter()
         .combinations(2)
         .max_by_key(|x| get_area(x[0], x[1]))
         .unwrap();
-
-    println!("{:?}", get_area(max_rectangle[0], max_rectangle[1]));
+    (
+        *max_rectangle[0],
+        *max_rectangle[1],
+        get_area(max_rectangle[0], max_rectangle[1]),
+    )
 }
 
-fn get_max_coord(points: &[Point], coord_getter: fn(&Point) -> usize) -> usize {
-    points.iter().map(coord_getter).max().unwrap()
+fn read_points(lines: &[String]) -> Vec<Point> {
+    lines
+        .iter()
+        .map(|line| Point::from(line.as_str()))
+        .collect()
 }
 
-struct RecChecker {
-    y_to_ranges: Vec<RangeSet2<usize>>,
+/// A rectilinear loop of about `size` red tiles: a stepped top edge left to right, then a
+/// stepped bottom edge back. Tops stay above the middle and bottoms below it, so the loop
+/// never crosses itself.
+pub fn generate(rng: &mut Rng, size: usize) -> Vec<String> {
+    const MAX_COORD: i64 = 99_999;
+    let columns = (size / 4).max(1);
+    let mut xs: Vec<i64> = Vec::with_capacity(columns + 1);
+    let step = (MAX_COORD / (columns as i64 + 1)).max(2);
+    let mut x = rng.range(1..=step);
+    for _ in 0..=columns {
+        xs.push(x);
+        x += rng.range(1..=step);
+    }
+    let middle = MAX_COORD / 2;
+    let mut tops: Vec<i64> = Vec::with_capacity(columns);
+    let mut bottoms: Vec<i64> = Vec::with_capacity(columns);
+    for i in 0..columns {
+        // Neighbouring columns need different heights, or the shared corner is not a corner.
+        let top = loop {
+            let top = rng.range(middle + 1..=MAX_COORD);
+            if i == 0 || tops[i - 1] != top {
+                break top;
+            }
+        };
+        let bottom = loop {
+            let bottom = rng.range(0..=middle - 1);
+            if i == 0 || bottoms[i - 1] != bottom {
+                break bottom;
+            }
+        };
+        tops.push(top);
+        bottoms.push(bottom);
+    }
+
+    let mut points = Vec::with_capacity(4 * columns);
+    for i in 0..columns {
+        points.push((xs[i], tops[i]));
+        points.push((xs[i + 1], tops[i]));
+    }
+    for i in (0..columns).rev() {
+        points.push((xs[i + 1], bottoms[i]));
+        points.push((xs[i], bottoms[i]));
+    }
+    points.dedup();
+    points
+        .into_iter()
+        .map(|(x, y)| format!("{},{}", x, y))
+        .collect()
 }
 
-impl RecChecker {
-    fn from_red_green_se(red_green_se: &RoaringTreemap, max_y_exclusive: usize) -> Self {
-        // let mut y_to_ranges: Vec<RangeSet2<usize>> = vec![RangeSet2::empty(); max_y_exclusive];
-        let mut y_to_ranges: Vec<RangeSet2<usize>> =
-            (0..max_y_exclusive).map(|_| RangeSet2::empty()).collect();
-        let mut total = 0i64;
-
-        red_green_se
-            .iter()
-            .map(|point| Point::from_u64_mod(point, MOD))
-            .for_each(|p| {
-                total += 1;
-                if total % 25_000_000 == 0 {
-                    info!(
-                        "Processed {} % red-green cells for RecChecker...",
-                        total as f64 / red_green_se.len() as f64 * 100.0
-                    );
-                }
-                y_to_ranges[p.y] |= RangeSet2::from(p.x..p.x + 1);
-            });
+pub fn solve(part: u8, lines: &[String]) -> String {
+    let points = read_points(lines);
+    match part {
+        1 => find_max_rectangle(&points).2 as usize,
+        _ => find_max_red_green_rectangle(&points).map_or(0, |(_, _, area)| area),
+    }
+    .to_string()
+}
 
-        let lenghts_debug: Vec<(usize, SmallVec<[usize; 2]>)> = y_to_ranges
-            .iter()
-            .enumerate()
-            .map(|(idx, rs)| (idx, rs.clone().into_inner()))
-            .collect::<Vec<_>>();
+/// Whether a tile is red or green: on the loop itself, or strictly inside by ray casting.
+fn tile_in_loop(points: &[Point], x: usize, y: usize) -> bool {
+    let mut crossings = 0;
+    for (a, b) in points.iter().circular_tuple_windows() {
+        let (min_x, max_x) = (a.x.min(b.x), a.x.max(b.x));
+        let (min_y, max_y) = (a.y.min(b.y), a.y.max(b.y));
+        if (min_x..=max_x).contains(&x) && (min_y..=max_y).contains(&y) {
+            return true;
+        }
+        // Vertical edges to the right, half-open in y so vertices are not counted twice.
+        if a.x == b.x && a.x > x && min_y <= y && y < max_y {
+            crossings += 1;
+        }
+    }
+    crossings % 2 == 1
+}
 
-        for (idx, p) in lenghts_debug.iter().enumerate() {
-            if idx % 100 != 0 {
+/// Reference answer with plain geometry. Tile validity only changes at a loop coordinate or
+/// right next to one, so each rectangle is checked on those rows and columns alone.
+pub fn solve_naive(part: u8, lines: &[String]) -> String {
+    let points = read_points(lines);
+    let mut best = 0;
+    for (i, p1) in points.iter().enumerate() {
+        for p2 in &points[i + 1..] {
+            let area = rec_area(p1, p2);
+            if area <= best {
                 continue;
             }
-            println!("Y={} has {} ranges: {:?}", p.0, p.1.len(), p.1);
-            println!("{:?}", y_to_ranges[p.0]);
+            if part == 1 {
+                best = area;
+                continue;
+            }
+            let candidates = |a: usize, b: usize, coord: fn(&Point) -> usize| -> Vec<usize> {
+                let (low, high) = (a.min(b), a.max(b));
+                points
+                    .iter()
+                    .flat_map(|p| [coord(p).saturating_sub(1), coord(p), coord(p) + 1])
+                    .chain([low, high])
+                    .filter(|c| (low..=high).contains(c))
+                    .unique()
+                    .collect()
+            };
+            let xs = candidates(p1.x, p2.x, |p| p.x);
+            let ys = candidates(p1.y, p2.y, |p| p.y);
+            if xs
+                .iter()
+                .all(|&x| ys.iter().all(|&y| tile_in_loop(&points, x, y)))
+            {
+                best = area;
+            }
         }
+    }
+    best.to_string()
+}
 
-        RecChecker { y_to_ranges }
+pub fn main_part1(points: &[Point]) {
+    let (_, _, area) = find_max_rectangle(points);
+    println!("{:?}", area);
+}
+
+/// Maps every distinct coordinate to its own band and every non-empty gap between two
+/// consecutive coordinates to one extra band. Bands `0` and `len - 1` are padding that
+/// lies strictly outside the loop, so the flood fill can walk around it.
+fn compress_axis(mut coords: Vec<usize>) -> (HashMap<usize, usize>, usize) {
+    coords.sort_unstable();
+    coords.dedup();
+
+    let mut coord_to_band = HashMap::with_capacity(coords.len());
+    let mut band = 1usize;
+    for (i, &coord) in coords.iter().enumerate() {
+        if i > 0 && coord > coords[i - 1] + 1 {
+            band += 1;
+        }
+        coord_to_band.insert(coord, band);
+        band += 1;
     }
+    (coord_to_band, band + 1)
+}
 
-    fn is_rec_in(&self, p1: &Point, p2: &Point) -> bool {
-        let min_x = p1.x.min(p2.x);
-        let max_x = p1.x.max(p2.x);
-        let min_y = p1.y.min(p2.y);
-        let max_y = p1.y.max(p2.y);
-        for y in min_y..=max_y {
-            let range_set = &self.y_to_ranges[y];
-            let query_range = RangeSet2::from(min_x..max_x + 1);
-            if !query_range.is_subset(range_set) {
-                return false;
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Band {
+    Unknown,
+    Boundary,
+    Outside,
+}
+
+struct CompressedFloor {
+    x_bands: HashMap<usize, usize>,
+    y_bands: HashMap<usize, usize>,
+    // outside_prefix[y][x] is the number of outside cells in bands [0, x) x [0, y).
+    outside_prefix: Vec<Vec<usize>>,
+}
+
+impl CompressedFloor {
+    fn new(points: &[Point]) -> Self {
+        let (x_bands, width) = compress_axis(points.iter().map(|p| p.x).collect());
+        let (y_bands, height) = compress_axis(points.iter().map(|p| p.y).collect());
+        let to_band = |p: &Point| Point {
+            x: x_bands[&p.x],
+            y: y_bands[&p.y],
+        };
+
+        let mut cells = vec![vec![Band::Unknown; width]; height];
+        for (from, to) in points.iter().circular_tuple_windows() {
+            let from = to_band(from);
+            cells[from.y][from.x] = Band::Boundary;
+            from.walk_to(&to_band(to))
+                .for_each(|p| cells[p.y][p.x] = Band::Boundary);
+        }
+
+        let mut queue = VecDeque::from([Point { x: 0, y: 0 }]);
+        cells[0][0] = Band::Outside;
+        while let Some(point) = queue.pop_front() {
+            checkpoint();
+            for next in point.adjacent_neighbours(width, height) {
+                if cells[next.y][next.x] == Band::Unknown {
+                    cells[next.y][next.x] = Band::Outside;
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        let mut outside_prefix = vec![vec![0usize; width + 1]; height + 1];
+        for y in 0..height {
+            for x in 0..width {
+                let is_outside = (cells[y][x] == Band::Outside) as usize;
+                outside_prefix[y + 1][x + 1] = outside_prefix[y][x + 1] + outside_prefix[y + 1][x]
+                    - outside_prefix[y][x]
+                    + is_outside;
             }
         }
-        true
+
+        CompressedFloor {
+            x_bands,
+            y_bands,
+            outside_prefix,
+        }
+    }
+
+    /// Whether every tile of the rectangle spanned by two red tiles is red or green.
+    fn is_rec_in(&self, p1: &Point, p2: &Point) -> bool {
+        let (x1, x2) = (self.x_bands[&p1.x], self.x_bands[&p2.x]);
+        let (y1, y2) = (self.y_bands[&p1.y], self.y_bands[&p2.y]);
+        let (min_x, max_x) = (x1.min(x2), x1.max(x2) + 1);
+        let (min_y, max_y) = (y1.min(y2), y1.max(y2) + 1);
+        let prefix = &self.outside_prefix;
+        prefix[max_y][max_x] + prefix[min_y][min_x] - prefix[min_y][max_x] - prefix[max_y][min_x]
+            == 0
     }
 }
 
@@ -243,172 +399,266 @@ fn rec_area(p1: &Point, p2: &Point) -> usize {
 
 #[test]
 fn is_subset() {
-    let s1 = RangeSet2::from(0..5);
-    let s2 = RangeSet2::from(1..4);
+    let s1 = range_collections::RangeSet2::from(0..5);
+    let s2 = range_collections::RangeSet2::from(1..4);
     assert!(s2.is_subset(&s1));
 }
 
-fn build_red_green_se(
-    visited_outer: &RoaringTreemap,
-    max_x_exclusive: usize,
-    max_y_exclusive: usize,
-) -> RoaringTreemap {
-    let mut total = 0i64;
-    let should_process = (max_x_exclusive as i64) * (max_y_exclusive as i64);
-
-    let red_green: RoaringTreemap = (0..max_x_exclusive)
-        .cartesian_product(0..max_y_exclusive)
-        .map(|(x, y)| Point { x, y }.to_u64_mod(MOD))
-        .filter(|p_repr| {
-            total += 1;
-            if total % 25_000_000 == 0 {
-                info!(
-                    "Processed {}% cells for red-green set...",
-                    total as f64 / should_process as f64 * 100.0
-                );
+/// Two opposite red corners and the area of the rectangle between them.
+type Rectangle = (Point, Point, usize);
+
+/// Tries rectangles from the largest area down and stops at the first one inside the
+/// loop. With `keep_rejected`, also returns every larger candidate that was turned down.
+fn search_red_green_rectangles(
+    points: &[Point],
+    keep_rejected: bool,
+) -> (Option<Rectangle>, Vec<Rectangle>) {
+    let floor = CompressedFloor::new(points);
+    let mut rejected = Vec::new();
+    let best = points
+        .iter()
+        .tuple_combinations()
+        .map(|(p1, p2)| (*p1, *p2, rec_area(p1, p2)))
+        .sorted_by_key(|&(_, _, area)| std::cmp::Reverse(area))
+        .find(|rectangle| {
+            checkpoint();
+            let fits = floor.is_rec_in(&rectangle.0, &rectangle.1);
+            if !fits && keep_rejected {
+                rejected.push(*rectangle);
             }
-            !visited_outer.contains(*p_repr)
-        })
-        .collect();
+            fits
+        });
+    (best, rejected)
+}
 
-    red_green
+fn find_max_red_green_rectangle(points: &[Point]) -> Option<Rectangle> {
+    search_red_green_rectangles(points, false).0
 }
 
-fn print_in_set(p: Point, visited_outer: &RoaringTreemap) {
-    let is_in = visited_outer.contains(p.to_u64_mod(MOD));
-    println!("Point {:?} is in visited_outer: {}", p, is_in);
+#[test]
+fn test_max_red_green_rectangle() {
+    let points: Vec<Point> = ["7,1", "11,1", "11,7", "9,7", "9,5", "2,5", "2,3", "7,3"]
+        .into_iter()
+        .map(Point::from)
+        .collect();
+    let (_, _, area) = find_max_red_green_rectangle(&points).unwrap();
+    assert_eq!(area, 24);
 }
 
-pub fn main_part2(mut points: Vec<Point>) {
-    let (max_field_x_exclusive, max_field_y_exclusive) = (
-        get_max_coord(&points, |p| p.x) + 3usize,
-        get_max_coord(&points, |p| p.y) + 3usize,
-    );
+#[test]
+fn test_max_red_green_rectangle_across_touching_edges() {
+    // A "U" whose slot is zero tiles wide: the rectangle spanning the slot stays green.
+    let points: Vec<Point> = ["0,0", "5,0", "5,4", "6,4", "6,0", "9,0", "9,9", "0,9"]
+        .into_iter()
+        .map(Point::from)
+        .collect();
+    let (_, _, area) = find_max_red_green_rectangle(&points).unwrap();
+    assert_eq!(area, 100);
+}
 
-    let mut colored_points = RoaringTreemap::new();
-    let first_point = points[0];
-    points.push(first_point);
+const SVG_VIEWPORT: f64 = 1000.0;
+const SVG_MARGIN: f64 = 40.0;
 
-    points.windows(2).for_each(|w| {
-        colored_points.insert(w[0].to_u64_mod(MOD));
-        let greens_between = w[0].manhattan_distance(&w[1]).checked_sub(1).unwrap();
-        w[0].walk_to(&w[1]).take(greens_between).for_each(|p| {
-            colored_points.insert(p.to_u64_mod(MOD));
-        });
-    });
-
-    let mut visited_outer: RoaringTreemap = RoaringTreemap::new();
-    let file_outer_path = "/Users/iv/Desktop/visited_outer2.roaring";
-    let file_inner_path = "/Users/iv/Desktop/visited_inner2.roaring";
-    // let file_outer_path = "/Users/iv/Desktop/visited_outer_test.roaring";
-    // let file_inner_path = "/Users/iv/Desktop/visited_inner_test.roaring";
-
-    // // Building the visited_outer set
-    {
-        let mut q = VecDeque::from([Point { x: 0, y: 0 }]);
-        let mut iterations: i64 = 0;
-        while let Some(point) = q.pop_front() {
-            iterations += 1;
-            if iterations % 25_000_000 == 0 {
-                println!(
-                    "Iterations: {}, queue size: {}, visited size: {}",
-                    iterations,
-                    q.len(),
-                    visited_outer.len()
-                );
-            }
-            if visited_outer.contains(point.to_u64_mod(MOD)) {
-                continue;
-            }
-            visited_outer.insert(point.to_u64_mod(MOD));
-            let new_points = point
-                .adjacent_neighbours(max_field_x_exclusive + 1, max_field_y_exclusive + 1)
-                .filter(|p| {
-                    !colored_points.contains(p.to_u64_mod(MOD))
-                        && !visited_outer.contains(p.to_u64_mod(MOD))
-                });
-            q.extend(new_points);
-        }
+/// The tile loop scaled into a fixed viewport: red tiles as dots, the green boundary and
+/// interior as a polygon, and the best rectangle of each part on top. Rejected part 2
+/// candidates are drawn faintly underneath, with their area as a tooltip.
+fn render_svg(
+    points: &[Point],
+    best_part1: (Point, Point, i64),
+    best_part2: Option<Rectangle>,
+    rejected: &[Rectangle],
+) -> Svg {
+    let min_x = points.iter().map(|p| p.x).min().unwrap_or(0);
+    let min_y = points.iter().map(|p| p.y).min().unwrap_or(0);
+    let span_x = points.iter().map(|p| p.x).max().unwrap_or(0) - min_x + 1;
+    let span_y = points.iter().map(|p| p.y).max().unwrap_or(0) - min_y + 1;
+    let scale = SVG_VIEWPORT / span_x.max(span_y) as f64;
+    // Tile (x, y) covers [x, x + 1) x [y, y + 1) before scaling.
+    let to_svg = |x: f64, y: f64| {
+        (
+            (x - min_x as f64) * scale + SVG_MARGIN,
+            (y - min_y as f64) * scale + SVG_MARGIN,
+        )
+    };
+    let rectangle_bounds = |p1: &Point, p2: &Point| {
+        let (x1, y1) = to_svg(p1.x.min(p2.x) as f64, p1.y.min(p2.y) as f64);
+        let (x2, y2) = to_svg(p1.x.max(p2.x) as f64 + 1.0, p1.y.max(p2.y) as f64 + 1.0);
+        (x1, y1, x2 - x1, y2 - y1)
+    };
 
-        let mut f = File::create(file_outer_path).unwrap();
-        info!("Writing visited_outer to {} ...", file_outer_path);
-        visited_outer.serialize_into(&mut f).unwrap();
+    let mut svg = Svg::new(
+        span_x as f64 * scale + SVG_MARGIN * 2.0,
+        span_y as f64 * scale + SVG_MARGIN * 2.0,
+    );
+    for (p1, p2, area) in rejected {
+        let (x, y, w, h) = rectangle_bounds(p1, p2);
+        let title = format!("rejected: {:?} - {:?}, area {}", p1, p2, area);
+        svg.rect(
+            x,
+            y,
+            w,
+            h,
+            "fill:none;stroke:#999;stroke-opacity:0.3;stroke-width:0.5",
+            Some(&title),
+        );
     }
-    {
-        let f = File::open(file_outer_path).unwrap();
-        info!("Deserializing visited_outer from file...");
-        visited_outer = RoaringTreemap::deserialize_from(f).unwrap()
+
+    let outline: Vec<(f64, f64)> = points
+        .iter()
+        .map(|p| to_svg(p.x as f64 + 0.5, p.y as f64 + 0.5))
+        .collect();
+    svg.polygon(&outline, "fill:#c8f0c8;stroke:#2a9d2a;stroke-width:1.5");
+    let dot = scale.max(3.0);
+    for p in points {
+        let (x, y) = to_svg(p.x as f64 + 0.5, p.y as f64 + 0.5);
+        svg.rect(
+            x - dot / 2.0,
+            y - dot / 2.0,
+            dot,
+            dot,
+            "fill:#d62828",
+            Some(&format!("{:?}", p)),
+        );
     }
 
-    println!(
-        "Total cells: {}",
-        (max_field_x_exclusive) as i64 * (max_field_y_exclusive) as i64
+    let (p1, p2, area) = best_part1;
+    let (x, y, w, h) = rectangle_bounds(&p1, &p2);
+    let title = format!("part 1: {:?} - {:?}, area {}", p1, p2, area);
+    svg.rect(
+        x,
+        y,
+        w,
+        h,
+        "fill:none;stroke:#1d4ed8;stroke-width:2;stroke-dasharray:8 4",
+        Some(&title),
     );
-    println!("Colored cells: {}", colored_points.len() as i64);
-    println!("Outer empty cells: {}", visited_outer.len() as i64);
-
-    let mut red_green_se = RoaringTreemap::new();
-    {
-        info!(
-            "Building red-green set, initial len: {} ...",
-            red_green_se.len()
+    if let Some((p1, p2, area)) = best_part2 {
+        let (x, y, w, h) = rectangle_bounds(&p1, &p2);
+        let title = format!("part 2: {:?} - {:?}, area {}", p1, p2, area);
+        svg.rect(
+            x,
+            y,
+            w,
+            h,
+            "fill:#f59e0b;fill-opacity:0.35;stroke:#f59e0b;stroke-width:3",
+            Some(&title),
         );
-        red_green_se =
-            build_red_green_se(&visited_outer, max_field_x_exclusive, max_field_y_exclusive);
+    }
 
-        let mut f = File::create(file_inner_path).unwrap();
-        info!("Writing red-green set to {} ...", file_inner_path);
-        red_green_se.serialize_into(&mut f).unwrap();
-        info!("Wrote red-green set to {} ...", file_inner_path);
+    let legend = format!(
+        "part 1 (blue, dashed): area {}; part 2 (orange): {}; {} rejected",
+        area,
+        best_part2.map_or("none".to_string(), |(_, _, area)| format!("area {}", area)),
+        rejected.len()
+    );
+    svg.text(
+        SVG_MARGIN,
+        SVG_MARGIN / 2.0,
+        &legend,
+        "font-family:monospace;font-size:14px",
+    );
+    svg
+}
+
+#[test]
+fn test_render_svg() {
+    let points: Vec<Point> = ["7,1", "11,1", "11,7", "9,7", "9,5", "2,5", "2,3", "7,3"]
+        .into_iter()
+        .map(Point::from)
+        .collect();
+    let (best, rejected) = search_red_green_rectangles(&points, true);
+    let rendered = render_svg(&points, find_max_rectangle(&points), best, &rejected).render();
+    assert!(rendered.contains("part 1 (blue, dashed): area 50; part 2 (orange): area 24"));
+    assert!(rendered.contains("rejected: Point { x: 11, y: 1 } - Point { x: 2, y: 5 }, area 50"));
+    assert_eq!(rendered.matches("rejected: ").count(), rejected.len());
+}
+
+pub fn main_part2(points: &[Point]) {
+    match find_max_red_green_rectangle(points) {
+        Some((p1, p2, area)) => println!("Best pair: {:?} with area {}", (p1, p2), area),
+        None => println!("No rectangle fits inside the loop"),
     }
-    {
-        let f = File::open(file_inner_path).unwrap();
-        info!("Deserializing red-green from file...");
-        red_green_se = RoaringTreemap::deserialize_from(f).unwrap()
+}
+
+/// Red tiles as `x,y`, each sharing a row or a column with the next one (and the last one
+/// with the first), so the tiles close a loop of straight lines.
+pub fn validate(lines: &[String]) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    let mut tiles: Vec<(usize, Point)> = Vec::new();
+    for (line, tile) in numbered(lines) {
+        let Some((x, y)) = tile.split_once(',') else {
+            problems.push(Problem::at(line, format!("'{}' is not an x,y tile", tile)));
+            continue;
+        };
+        let x = parse::<usize>(line, x, "a coordinate", &mut problems);
+        let y = parse::<usize>(line, y, "a coordinate", &mut problems);
+        if let (Some(x), Some(y)) = (x, y) {
+            tiles.push((line, Point { x, y }));
+        }
     }
-    info!(
-        "Red-green set built with {} cells. Building checker ...",
-        red_green_se.len()
-    );
-    // print_in_set(Point{x: 90000, y: 90000}, &red_green_se);
-    for i in 0..100 {
-        print_in_set(
-            Point {
-                x: i * 1000,
-                y: 90000,
-            },
-            &visited_outer,
-        );
+    if tiles.len() < 2 {
+        problems.push(Problem::input("There should be at least two red tiles"));
+        return problems;
     }
-    let rec_checker = RecChecker::from_red_green_se(&red_green_se, max_field_y_exclusive);
-    let mut max_area: usize = 0;
-    let mut best_pair: Option<(Point, Point)> = None;
-
-    points.iter().combinations(2).for_each(|pair| {
-        let p1 = pair[0];
-        let p2 = pair[1];
-        let area = rec_area(p1, p2);
-        if area > max_area && rec_checker.is_rec_in(p1, p2) {
-            max_area = area;
-            println!(
-                "New max area: {} for points {:?} and {:?}",
-                max_area, p1, p2
-            );
-            best_pair = Some((*p1, *p2));
+
+    for (i, &(line, tile)) in tiles.iter().enumerate() {
+        let (next_line, next) = tiles[(i + 1) % tiles.len()];
+        if tile == next {
+            problems.push(Problem::at(
+                next_line,
+                format!(
+                    "Tile {},{} repeats the one on line {}",
+                    next.x, next.y, line
+                ),
+            ));
+        } else if tile.x != next.x && tile.y != next.y {
+            problems.push(Problem::at(
+                next_line,
+                format!(
+                    "Tile {},{} shares neither row nor column with {},{} on line {}",
+                    next.x, next.y, tile.x, tile.y, line
+                ),
+            ));
         }
-    });
-    println!("Best pair: {:?} with area {}", best_pair, max_area);
+    }
+    problems
+}
+
+#[test]
+fn test_validate() {
+    assert!(validate(&read_input_lines(9, Some("test"))).is_empty());
+    let lines = ["7,1", "11,1", "11,1", "9,7", "7"].map(String::from);
+    let problems = validate(&lines).iter().map(|p| p.to_string()).collect_vec();
+    assert_eq!(
+        problems,
+        [
+            "line 5: '7' is not an x,y tile",
+            "line 3: Tile 11,1 repeats the one on line 2",
+            "line 4: Tile 9,7 shares neither row nor column with 11,1 on line 3",
+            "line 1: Tile 7,1 shares neither row nor column with 9,7 on line 4",
+        ]
+    );
 }
 
 pub fn main(args: &Args) {
     let lines = read_input_lines(args.day as u32, args.input_tag.as_deref());
-    let points: Vec<_> = lines
-        .into_iter()
-        .map(|line| Point::from(line.as_str()))
-        .collect::<Vec<_>>();
+    let points = read_points(&lines);
     match args.part {
-        1 => main_part1(points),
-        2 => main_part2(points),
+        1 => main_part1(&points),
+        2 => main_part2(&points),
         _ => panic!("Part {} not implemented", args.part),
     }
+
+    if let Some(path) = &args.svg {
+        let (best_part2, rejected) = search_red_green_rectangles(&points, args.show_rejected);
+        render_svg(&points, find_max_rectangle(&points), best_part2, &rejected)
+            .write_to(path)
+            .unwrap_or_else(|e| panic!("Unable to write SVG to {}: {e}", path.display()));
+        info!("Wrote the tile loop to {}", path.display());
+    }
+}
+
+#[test]
+fn test_examples() {
+    crate::days::check_example(9, "test");
 }
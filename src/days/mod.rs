@@ -10,3 +10,168 @@ pub mod day6;
 pub mod day7;
 pub mod day8;
 pub mod day9;
+
+use crate::utils::rng::Rng;
+use crate::utils::validate::Problem;
+
+/// Answer for one day and part on the given input lines, with every option at its default.
+pub fn solve(day: u8, part: u8, lines: &[String]) -> String {
+    match day {
+        1 => day1::solve(part, lines),
+        2 => day2::solve(part, lines),
+        3 => day3::solve(part, lines),
+        4 => day4::solve(part, lines),
+        5 => day5::solve(part, lines),
+        6 => day6::solve(part, lines),
+        7 => day7::solve(part, lines),
+        8 => day8::solve(part, lines),
+        9 => day9::solve(part, lines),
+        10 => day10::solve(part, lines),
+        11 => day11::solve(part, lines),
+        12 => day12::solve(part, lines),
+        _ => panic!("Day {} is not yet implemented", day),
+    }
+}
+
+/// Random input for a day; see each day's `generate` for what `size` means there.
+pub fn generate(day: u8, rng: &mut Rng, size: usize) -> Vec<String> {
+    match day {
+        1 => day1::generate(rng, size),
+        2 => day2::generate(rng, size),
+        3 => day3::generate(rng, size),
+        4 => day4::generate(rng, size),
+        5 => day5::generate(rng, size),
+        6 => day6::generate(rng, size),
+        7 => day7::generate(rng, size),
+        8 => day8::generate(rng, size),
+        9 => day9::generate(rng, size),
+        10 => day10::generate(rng, size),
+        11 => day11::generate(rng, size),
+        12 => day12::generate(rng, size),
+        _ => panic!("Day {} is not yet implemented", day),
+    }
+}
+
+/// Runs every part listed in the example's answers file and compares the results.
+#[cfg(test)]
+pub(crate) fn check_example(day: u8, tag: &str) {
+    use crate::utils::input::{read_expected_answers, read_input_lines};
+
+    let lines = read_input_lines(day as u32, Some(tag));
+    let expected = read_expected_answers(day as u32, Some(tag));
+    assert!(!expected.is_empty(), "No answers for day {} '{}'", day, tag);
+    for (part, answer) in expected {
+        assert_eq!(
+            solve(day, part, &lines),
+            answer,
+            "Day {} part {} on '{}'",
+            day,
+            part,
+            tag
+        );
+    }
+}
+
+#[test]
+fn test_generated_inputs_solve() {
+    for day in 1..=12 {
+        for seed in 0..3 {
+            let lines: Vec<String> = generate(day, &mut Rng::new(seed), 12)
+                .into_iter()
+                .filter(|line| !line.is_empty())
+                .collect();
+            for part in 1..=2 {
+                let answer = solve(day, part, &lines);
+                assert!(
+                    !answer.is_empty(),
+                    "Day {} part {} seed {}",
+                    day,
+                    part,
+                    seed
+                );
+            }
+        }
+    }
+}
+
+/// Reference answer from the day's deliberately simple solver, for differential testing.
+pub fn solve_naive(day: u8, part: u8, lines: &[String]) -> String {
+    match day {
+        1 => day1::solve_naive(part, lines),
+        2 => day2::solve_naive(part, lines),
+        3 => day3::solve_naive(part, lines),
+        4 => day4::solve_naive(part, lines),
+        5 => day5::solve_naive(part, lines),
+        6 => day6::solve_naive(part, lines),
+        7 => day7::solve_naive(part, lines),
+        8 => day8::solve_naive(part, lines),
+        9 => day9::solve_naive(part, lines),
+        10 => day10::solve_naive(part, lines),
+        11 => day11::solve_naive(part, lines),
+        12 => day12::solve_naive(part, lines),
+        _ => panic!("Day {} is not yet implemented", day),
+    }
+}
+
+#[test]
+fn test_naive_examples() {
+    use crate::utils::input::{read_expected_answers, read_input_lines};
+    // Day 12's plain search needs seconds on the region that does not fit in debug builds,
+    // and day 8's example connects fewer pairs than `solve_naive` does; it tests its own.
+    for (day, tag) in (1..=11)
+        .filter(|&d| d != 8)
+        .map(|d| (d, "test"))
+        .chain([(11, "test2")])
+    {
+        let lines = read_input_lines(day as u32, Some(tag));
+        for (part, answer) in read_expected_answers(day as u32, Some(tag)) {
+            assert_eq!(
+                solve_naive(day, part, &lines),
+                answer,
+                "Day {} part {}",
+                day,
+                part
+            );
+        }
+    }
+}
+
+/// Problems with a day's input, given every line of the file including the empty ones.
+pub fn validate(day: u8, lines: &[String]) -> Vec<Problem> {
+    match day {
+        1 => day1::validate(lines),
+        2 => day2::validate(lines),
+        3 => day3::validate(lines),
+        4 => day4::validate(lines),
+        5 => day5::validate(lines),
+        6 => day6::validate(lines),
+        7 => day7::validate(lines),
+        8 => day8::validate(lines),
+        9 => day9::validate(lines),
+        10 => day10::validate(lines),
+        11 => day11::validate(lines),
+        12 => day12::validate(lines),
+        _ => panic!("Day {} is not yet implemented", day),
+    }
+}
+
+#[test]
+fn test_examples_and_generated_inputs_validate() {
+    use crate::utils::input::{get_input_path, read_raw_lines};
+    for (day, tag) in (1..=12).map(|d| (d, "test")).chain([(11, "test2")]) {
+        let problems = validate(day, &read_raw_lines(&get_input_path(day as u32, Some(tag))));
+        assert!(problems.is_empty(), "Day {} '{}': {:?}", day, tag, problems);
+    }
+    for day in 1..=12 {
+        for seed in 0..3 {
+            let problems = validate(day, &generate(day, &mut Rng::new(seed), 12));
+            assert!(
+                problems.is_empty(),
+                "Day {} seed {}: {:?}",
+                day,
+                seed,
+                problems
+            );
+        }
+    }
+}
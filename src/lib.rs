@@ -1,3 +1,4 @@
 pub mod args;
 pub mod days;
+pub mod tools;
 pub mod utils;
@@ -1,25 +1,307 @@
-use aoc_2025::args::Args;
+use aoc_2025::args::{
+    Args, BenchArgs, Cli, Command, DiffArgs, Failure, GenArgs, MinimizeArgs, ValidateArgs,
+};
 use aoc_2025::days;
-use clap::Parser;
+use aoc_2025::tools::alloc;
+use aoc_2025::tools::bench::{self, BenchRecord, BenchResult, RunStatus, Verdict};
+use aoc_2025::tools::differential::{find_disagreement, parts};
+use aoc_2025::tools::minimize::{failure_on, minimize, solver_lines};
+use aoc_2025::utils::cancel::{self, Outcome};
+use aoc_2025::utils::input::{get_input_path, get_repo_root, read_input_lines, read_raw_lines};
+use aoc_2025::utils::rng::Rng;
+use clap::{CommandFactory, Parser};
+use log::info;
 use simple_logger::SimpleLogger;
+use std::sync::Arc;
+use std::time::Duration;
 
 fn main() {
     SimpleLogger::new().init().unwrap();
 
-    let args = Args::parse();
+    let cli = Cli::parse();
+    match (cli.command, cli.run) {
+        (Some(Command::Gen(gen_args)), _) => generate(&gen_args),
+        (Some(Command::Diff(diff_args)), _) => differential(&diff_args),
+        (Some(Command::Validate(validate_args)), _) => validate(&validate_args),
+        (Some(Command::Minimize(minimize_args)), _) => shrink(&minimize_args),
+        (Some(Command::Bench(bench_args)), _) => benchmark(&bench_args),
+        (None, Some(args)) => run(&args),
+        (None, None) => {
+            Cli::command().print_help().unwrap();
+            std::process::exit(2);
+        }
+    }
+}
+
+fn run(args: &Args) {
     match args.day {
-        1 => days::day1::main(&args),
-        2 => days::day2::main(&args),
-        3 => days::day3::main(&args),
-        4 => days::day4::main(&args),
-        5 => days::day5::main(&args),
-        6 => days::day6::main(&args),
-        7 => days::day7::main(&args),
-        8 => days::day8::main(&args),
-        9 => days::day9::main(&args),
-        10 => days::day10::main(&args),
-        11 => days::day11::main(&args),
-        12 => days::day12::main(&args),
+        1 => days::day1::main(args),
+        2 => days::day2::main(args),
+        3 => days::day3::main(args),
+        4 => days::day4::main(args),
+        5 => days::day5::main(args),
+        6 => days::day6::main(args),
+        7 => days::day7::main(args),
+        8 => days::day8::main(args),
+        9 => days::day9::main(args),
+        10 => days::day10::main(args),
+        11 => days::day11::main(args),
+        12 => days::day12::main(args),
         _ => panic!("Day {} is not yet implemented", args.day),
     }
 }
+
+fn generate(gen_args: &GenArgs) {
+    let mut rng = Rng::new(gen_args.seed);
+    let lines = days::generate(gen_args.day, &mut rng, gen_args.size);
+    let path = gen_args
+        .output
+        .clone()
+        .unwrap_or_else(|| get_input_path(gen_args.day as u32, Some(&gen_args.tag)));
+    let mut contents = lines.join("\n");
+    contents.push('\n');
+    std::fs::write(&path, contents)
+        .unwrap_or_else(|e| panic!("Unable to write {}: {e}", path.display()));
+    info!("Wrote {} lines to {}", lines.len(), path.display());
+}
+
+fn differential(diff_args: &DiffArgs) {
+    // The solvers log their progress, which would bury the verdict across many seeds.
+    log::set_max_level(log::LevelFilter::Warn);
+    let days: Vec<u8> = diff_args
+        .day
+        .map_or_else(|| (1..=12).collect(), |day| vec![day]);
+    let seeds = diff_args.seed..diff_args.seed + diff_args.runs;
+    let Some(disagreement) =
+        find_disagreement(&days, seeds, diff_args.size, diff_args.joltage_solver)
+    else {
+        println!(
+            "Fast and naive solvers agree on {} generated inputs per day",
+            diff_args.runs
+        );
+        return;
+    };
+
+    let path = get_input_path(disagreement.day as u32, Some("diff"));
+    let mut contents = disagreement.lines.join("\n");
+    contents.push('\n');
+    std::fs::write(&path, contents)
+        .unwrap_or_else(|e| panic!("Unable to write {}: {e}", path.display()));
+    println!(
+        "Day {} part {} disagrees on seed {}: fast {}, naive {}",
+        disagreement.day,
+        disagreement.part,
+        disagreement.seed,
+        disagreement.fast,
+        disagreement.naive
+    );
+    println!("The input is in {}", path.display());
+    std::process::exit(1);
+}
+
+fn validate(validate_args: &ValidateArgs) {
+    let path = validate_args.input.clone().unwrap_or_else(|| {
+        get_input_path(validate_args.day as u32, validate_args.input_tag.as_deref())
+    });
+    let problems = days::validate(validate_args.day, &read_raw_lines(&path));
+    if problems.is_empty() {
+        println!(
+            "{} is a valid day {} input",
+            path.display(),
+            validate_args.day
+        );
+        return;
+    }
+
+    for problem in &problems {
+        println!("{}: {}", path.display(), problem);
+    }
+    println!(
+        "{} problem{} in {}",
+        problems.len(),
+        if problems.len() == 1 { "" } else { "s" },
+        path.display()
+    );
+    std::process::exit(1);
+}
+
+fn shrink(minimize_args: &MinimizeArgs) {
+    let day = minimize_args.day;
+    let path = minimize_args
+        .input
+        .clone()
+        .unwrap_or_else(|| get_input_path(day as u32, minimize_args.input_tag.as_deref()));
+    let lines = read_raw_lines(&path);
+    let parts = minimize_args
+        .part
+        .map_or_else(|| parts(day).to_vec(), |part| vec![part]);
+    let failure = minimize_args.failure;
+
+    // The predicate panics on purpose again and again; only the outcome is interesting.
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(|_| {}));
+    let original = failure_on(day, &parts, failure, &solver_lines(&lines));
+    let minimal = original.as_ref().map(|original| {
+        let mut checks = 0;
+        let minimal = minimize(day, &lines, &mut |candidate| {
+            checks += 1;
+            failure_on(day, &parts, failure, candidate).as_ref() == Some(original)
+        });
+        info!("Checked {} candidate inputs", checks);
+        minimal
+    });
+    std::panic::set_hook(default_hook);
+
+    let Some(minimal) = minimal else {
+        let failure = match failure {
+            Failure::Panic => "panic",
+            Failure::Disagreement => "disagreement",
+        };
+        println!(
+            "No {} on day {} with {}, nothing to minimize",
+            failure,
+            day,
+            path.display()
+        );
+        std::process::exit(2);
+    };
+    let output = minimize_args
+        .output
+        .clone()
+        .unwrap_or_else(|| get_input_path(day as u32, Some("min")));
+    let mut contents = minimal.join("\n");
+    contents.push('\n');
+    std::fs::write(&output, contents)
+        .unwrap_or_else(|e| panic!("Unable to write {}: {e}", output.display()));
+    println!(
+        "Shrank {} lines to {}, written to {}",
+        lines.iter().filter(|line| !line.is_empty()).count(),
+        minimal.len(),
+        output.display()
+    );
+    println!("Failure kept: {}", original.unwrap());
+}
+
+fn benchmark(bench_args: &BenchArgs) {
+    // Solver logs would end up in the timings, and the logger writes to stdout too, which
+    // would break the JSON. Only set here, so runs left behind by a timeout can't change it.
+    let level = log::max_level();
+    log::set_max_level(log::LevelFilter::Warn);
+    cancel::silence_cancellations();
+    let timeout = bench_args.timeout.map(Duration::from_secs_f64);
+    let days: Vec<u8> = bench_args
+        .day
+        .map_or_else(|| (1..=12).collect(), |day| vec![day]);
+    let history_path = bench_args
+        .history
+        .clone()
+        .unwrap_or_else(|| get_repo_root().join("bench_history.tsv"));
+    let history = bench::read_history(&history_path);
+    let commit = bench::git_commit();
+    let recorded_at = bench::now();
+
+    let mut results = Vec::new();
+    for day in days {
+        let tag = bench_args.input_tag.as_deref();
+        let input_path = get_input_path(day as u32, tag);
+        let lines = Arc::new(read_input_lines(day as u32, tag));
+        let parts = bench_args
+            .part
+            .map_or_else(|| parts(day).to_vec(), |part| vec![part]);
+        for part in parts {
+            let runs = bench_args.runs;
+            let worker_lines = Arc::clone(&lines);
+            let outcome = cancel::run_with_timeout(timeout, move || {
+                let (_, alloc) = alloc::measure(|| days::solve(day, part, &worker_lines));
+                let samples = bench::time_runs(runs, || days::solve(day, part, &worker_lines));
+                (alloc, samples)
+            });
+            let (alloc, samples_ns, status) = match outcome {
+                Outcome::Finished((alloc, samples)) => (alloc, samples, RunStatus::Finished),
+                Outcome::TimedOut => (
+                    None,
+                    Vec::new(),
+                    RunStatus::TimedOut(timeout.unwrap_or_default()),
+                ),
+                Outcome::Panicked(message) => (None, Vec::new(), RunStatus::Panicked(message)),
+            };
+            let record = BenchRecord {
+                commit: commit.clone(),
+                recorded_at,
+                day,
+                part,
+                input: input_path
+                    .file_name()
+                    .unwrap()
+                    .to_string_lossy()
+                    .to_string(),
+                input_hash: bench::input_hash(&lines),
+                samples_ns,
+            };
+            let baseline = (bench_args.compare && status == RunStatus::Finished)
+                .then(|| bench::find_baseline(&history, &record))
+                .flatten()
+                .map(|baseline| {
+                    let comparison =
+                        bench::compare(baseline, &record, bench_args.threshold / 100.0);
+                    (baseline.clone(), comparison)
+                });
+            let result = BenchResult {
+                record,
+                status,
+                alloc,
+                baseline,
+            };
+            if !bench_args.json {
+                match &result.baseline {
+                    None if bench_args.compare && result.status == RunStatus::Finished => {
+                        println!("{}, no earlier commit to compare with", result.summary())
+                    }
+                    _ => println!("{}", result.summary()),
+                }
+            }
+            results.push(result);
+        }
+    }
+
+    if bench_args.json {
+        println!("{}", bench::results_to_json(&results));
+    }
+    // Runs without samples would only get in the way of later comparisons.
+    let records: Vec<BenchRecord> = results
+        .iter()
+        .filter(|r| r.status == RunStatus::Finished)
+        .map(|r| r.record.clone())
+        .collect();
+    bench::append_history(&history_path, &records);
+    if !bench_args.json {
+        log::set_max_level(level);
+    }
+    info!(
+        "Appended {} timings to {}",
+        records.len(),
+        history_path.display()
+    );
+    let regressions = results
+        .iter()
+        .filter(|r| matches!(&r.baseline, Some((_, c)) if c.verdict == Verdict::Regression))
+        .count();
+    let unfinished = results
+        .iter()
+        .filter(|r| r.status != RunStatus::Finished)
+        .count();
+    if !bench_args.json {
+        if regressions > 0 {
+            println!(
+                "{} regressions above {}%",
+                regressions, bench_args.threshold
+            );
+        }
+        if unfinished > 0 {
+            println!("{} parts timed out or panicked", unfinished);
+        }
+    }
+    if regressions > 0 || unfinished > 0 {
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,155 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+/// The system allocator plus counters for what a run allocates. Only installed with the
+/// `alloc-stats` feature, so normal builds don't pay for the bookkeeping.
+pub struct CountingAllocator;
+
+#[cfg(feature = "alloc-stats")]
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// Per thread, so a run only sees its own allocations and not those of other threads,
+/// such as a timed out run that is still going.
+#[derive(Debug, Clone, Copy)]
+struct Counters {
+    // Signed, as a thread may free memory another thread allocated.
+    live_bytes: i64,
+    peak_bytes: i64,
+    allocations: u64,
+    allocated_bytes: u64,
+}
+
+thread_local! {
+    // Const-initialised without a destructor, so using it never allocates.
+    static COUNTERS: Cell<Counters> = const {
+        Cell::new(Counters {
+            live_bytes: 0,
+            peak_bytes: 0,
+            allocations: 0,
+            allocated_bytes: 0,
+        })
+    };
+}
+
+impl CountingAllocator {
+    fn update(f: impl FnOnce(&mut Counters)) {
+        // Fails only while the thread is being torn down, when nobody is measuring.
+        let _ = COUNTERS.try_with(|counters| {
+            let mut current = counters.get();
+            f(&mut current);
+            counters.set(current);
+        });
+    }
+
+    fn record_allocation(size: usize) {
+        Self::update(|counters| {
+            counters.allocations += 1;
+            counters.allocated_bytes += size as u64;
+            counters.live_bytes += size as i64;
+            counters.peak_bytes = counters.peak_bytes.max(counters.live_bytes);
+        });
+    }
+
+    fn record_deallocation(size: usize) {
+        Self::update(|counters| counters.live_bytes -= size as i64);
+    }
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = unsafe { System.alloc(layout) };
+        if !ptr.is_null() {
+            Self::record_allocation(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = unsafe { System.alloc_zeroed(layout) };
+        if !ptr.is_null() {
+            Self::record_allocation(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) };
+        Self::record_deallocation(layout.size());
+    }
+
+    /// Counted as a new allocation of the new size that frees the old one.
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = unsafe { System.realloc(ptr, layout, new_size) };
+        if !new_ptr.is_null() {
+            Self::record_allocation(new_size);
+            Self::record_deallocation(layout.size());
+        }
+        new_ptr
+    }
+}
+
+/// Heap use of one run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct AllocStats {
+    /// Most bytes live at once, beyond what was already live when the run started.
+    pub peak_bytes: u64,
+    pub allocations: u64,
+    pub allocated_bytes: u64,
+}
+
+pub fn enabled() -> bool {
+    cfg!(feature = "alloc-stats")
+}
+
+/// Runs `f` and, with the `alloc-stats` feature, reports what it allocated on the calling
+/// thread.
+pub fn measure<T>(f: impl FnOnce() -> T) -> (T, Option<AllocStats>) {
+    CountingAllocator::update(|counters| counters.peak_bytes = counters.live_bytes);
+    let before = COUNTERS.with(Cell::get);
+
+    let result = f();
+    let after = COUNTERS.with(Cell::get);
+    let stats = AllocStats {
+        peak_bytes: (after.peak_bytes - before.live_bytes).max(0) as u64,
+        allocations: after.allocations - before.allocations,
+        allocated_bytes: after.allocated_bytes - before.allocated_bytes,
+    };
+    (result, enabled().then_some(stats))
+}
+
+/// Bytes in binary units, e.g. `1.5 MiB`.
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+#[test]
+fn test_measure() {
+    let (sum, stats) = measure(|| {
+        let values: Vec<u64> = std::hint::black_box((0..1000).collect());
+        values.iter().sum::<u64>()
+    });
+    assert_eq!(sum, 499_500);
+    match stats {
+        // Only this thread is counted, so other tests running alongside don't show up.
+        Some(stats) => {
+            assert_eq!(stats.allocations, 1);
+            assert_eq!(stats.allocated_bytes, 8000);
+            assert_eq!(stats.peak_bytes, 8000);
+        }
+        None => assert!(!enabled()),
+    }
+    assert_eq!(format_bytes(512), "512 B");
+    assert_eq!(format_bytes(3 * 1024 * 1024 / 2), "1.5 MiB");
+}
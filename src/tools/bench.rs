@@ -0,0 +1,488 @@
+use crate::tools::alloc::{AllocStats, format_bytes};
+use std::io::Write;
+use std::path::Path;
+use std::process::Command;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// |z| above this is significant at the 5% level (two-sided).
+const SIGNIFICANT_Z: f64 = 1.96;
+
+/// Timings of one day and part on one input at one commit, as a line of the history file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BenchRecord {
+    pub commit: String,
+    pub recorded_at: u64,
+    pub day: u8,
+    pub part: u8,
+    /// File name of the input, e.g. `day08.txt`.
+    pub input: String,
+    /// Hash of the input's lines, so a changed input is never compared with the old one.
+    pub input_hash: String,
+    pub samples_ns: Vec<u64>,
+}
+
+impl BenchRecord {
+    pub fn to_line(&self) -> String {
+        format!(
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            self.commit,
+            self.recorded_at,
+            self.day,
+            self.part,
+            self.input,
+            self.input_hash,
+            self.samples_ns
+                .iter()
+                .map(|ns| ns.to_string())
+                .collect::<Vec<_>>()
+                .join(",")
+        )
+    }
+
+    pub fn from_line(line: &str) -> Option<BenchRecord> {
+        let fields: Vec<&str> = line.split('\t').collect();
+        let [commit, recorded_at, day, part, input, input_hash, samples] = fields[..] else {
+            return None;
+        };
+        Some(BenchRecord {
+            commit: commit.to_string(),
+            recorded_at: recorded_at.parse().ok()?,
+            day: day.parse().ok()?,
+            part: part.parse().ok()?,
+            input: input.to_string(),
+            input_hash: input_hash.to_string(),
+            samples_ns: samples
+                .split(',')
+                .map(|ns| ns.parse().ok())
+                .collect::<Option<_>>()?,
+        })
+    }
+
+    pub fn median(&self) -> Duration {
+        let mut samples = self.samples_ns.clone();
+        samples.sort_unstable();
+        let middle = samples.len() / 2;
+        let median = match samples.len() {
+            0 => 0,
+            n if n % 2 == 0 => (samples[middle - 1] + samples[middle]) / 2,
+            _ => samples[middle],
+        };
+        Duration::from_nanos(median)
+    }
+
+    /// Whether `other` timed the same day and part on the same input.
+    pub fn same_benchmark(&self, other: &BenchRecord) -> bool {
+        self.day == other.day && self.part == other.part && self.input_hash == other.input_hash
+    }
+}
+
+/// FNV-1a over the lines, enough to tell inputs apart without another dependency.
+pub fn input_hash(lines: &[String]) -> String {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in lines.iter().flat_map(|line| line.bytes().chain([b'\n'])) {
+        hash = (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3);
+    }
+    format!("{:016x}", hash)
+}
+
+/// Short hash of `HEAD`, marked `-dirty` with uncommitted changes, or `unknown` outside git.
+pub fn git_commit() -> String {
+    let git = |args: &[&str]| {
+        Command::new("git")
+            .args(args)
+            .current_dir(env!("CARGO_MANIFEST_DIR"))
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+    };
+    match git(&["rev-parse", "--short", "HEAD"]) {
+        Some(commit)
+            if git(&["status", "--porcelain", "--untracked-files=no"])
+                .is_some_and(|status| !status.is_empty()) =>
+        {
+            format!("{}-dirty", commit)
+        }
+        Some(commit) => commit,
+        None => "unknown".to_string(),
+    }
+}
+
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs())
+}
+
+/// Runs `solver` once to warm up, then `runs` more times, timing each.
+pub fn time_runs(runs: usize, mut solver: impl FnMut() -> String) -> Vec<u64> {
+    std::hint::black_box(solver());
+    (0..runs)
+        .map(|_| {
+            let start = Instant::now();
+            std::hint::black_box(solver());
+            start.elapsed().as_nanos() as u64
+        })
+        .collect()
+}
+
+pub fn read_history(path: &Path) -> Vec<BenchRecord> {
+    std::fs::read_to_string(path)
+        .map(|contents| {
+            contents
+                .lines()
+                .filter_map(BenchRecord::from_line)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+pub fn append_history(path: &Path, records: &[BenchRecord]) {
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .unwrap_or_else(|e| panic!("Unable to open {}: {e}", path.display()));
+    for record in records {
+        writeln!(file, "{}", record.to_line())
+            .unwrap_or_else(|e| panic!("Unable to write {}: {e}", path.display()));
+    }
+}
+
+/// Latest timing of the same benchmark recorded at another commit.
+pub fn find_baseline<'a>(
+    history: &'a [BenchRecord],
+    current: &BenchRecord,
+) -> Option<&'a BenchRecord> {
+    history
+        .iter()
+        .rev()
+        .find(|record| record.same_benchmark(current) && record.commit != current.commit)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    Regression,
+    Improvement,
+    Unchanged,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Comparison {
+    /// Relative change of the median, e.g. `0.25` for 25% slower.
+    pub change: f64,
+    pub z: f64,
+    pub verdict: Verdict,
+}
+
+/// Flags a change of the median beyond `threshold` (a fraction) when the Mann-Whitney test
+/// also finds the two sets of samples significantly different.
+pub fn compare(baseline: &BenchRecord, current: &BenchRecord, threshold: f64) -> Comparison {
+    let before = baseline.median().as_secs_f64();
+    let change = if before > 0.0 {
+        current.median().as_secs_f64() / before - 1.0
+    } else {
+        0.0
+    };
+    let z = mann_whitney_z(&baseline.samples_ns, &current.samples_ns);
+    let verdict = if change > threshold && z > SIGNIFICANT_Z {
+        Verdict::Regression
+    } else if change < -threshold && z < -SIGNIFICANT_Z {
+        Verdict::Improvement
+    } else {
+        Verdict::Unchanged
+    };
+    Comparison { change, z, verdict }
+}
+
+/// Normal approximation of the Mann-Whitney U test; positive when `after` tends to be larger.
+/// Ties get their average rank.
+pub fn mann_whitney_z(before: &[u64], after: &[u64]) -> f64 {
+    let (n1, n2) = (before.len() as f64, after.len() as f64);
+    if before.is_empty() || after.is_empty() {
+        return 0.0;
+    }
+    let mut all: Vec<(u64, bool)> = before
+        .iter()
+        .map(|&ns| (ns, false))
+        .chain(after.iter().map(|&ns| (ns, true)))
+        .collect();
+    all.sort_unstable();
+
+    let mut after_rank_sum = 0.0;
+    let mut tie_correction = 0.0;
+    let mut i = 0;
+    while i < all.len() {
+        let tied = all[i..]
+            .iter()
+            .take_while(|(ns, _)| *ns == all[i].0)
+            .count();
+        let rank = i as f64 + (tied as f64 + 1.0) / 2.0;
+        after_rank_sum += rank * all[i..i + tied].iter().filter(|(_, a)| *a).count() as f64;
+        tie_correction += (tied.pow(3) - tied) as f64;
+        i += tied;
+    }
+
+    let u = after_rank_sum - n2 * (n2 + 1.0) / 2.0;
+    let n = n1 + n2;
+    let variance = n1 * n2 / 12.0 * ((n + 1.0) - tie_correction / (n * (n - 1.0)));
+    if variance <= 0.0 {
+        return 0.0;
+    }
+    (u - n1 * n2 / 2.0) / variance.sqrt()
+}
+
+/// How the timed runs of one day and part ended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RunStatus {
+    Finished,
+    /// Stopped after the `--timeout`; the record has no samples.
+    TimedOut(Duration),
+    /// The solver panicked with this message; the record has no samples.
+    Panicked(String),
+}
+
+/// What benchmarking one day and part found, for the summary line and the JSON output.
+#[derive(Debug, Clone)]
+pub struct BenchResult {
+    pub record: BenchRecord,
+    pub status: RunStatus,
+    /// Heap use of one extra run, with the `alloc-stats` feature.
+    pub alloc: Option<AllocStats>,
+    pub baseline: Option<(BenchRecord, Comparison)>,
+}
+
+impl BenchResult {
+    pub fn summary(&self) -> String {
+        let record = &self.record;
+        let benchmark = format!(
+            "Day {} part {} on {}",
+            record.day, record.part, record.input
+        );
+        match &self.status {
+            RunStatus::Finished => {}
+            RunStatus::TimedOut(after) => {
+                return format!("{}: timed out after {:?}", benchmark, after);
+            }
+            RunStatus::Panicked(message) => return format!("{}: panicked: {}", benchmark, message),
+        }
+        let mut summary = format!(
+            "{}: median {:?} over {} runs",
+            benchmark,
+            record.median(),
+            record.samples_ns.len()
+        );
+        if let Some(alloc) = self.alloc {
+            summary.push_str(&format!(
+                ", peak heap {}, {} allocations of {} in total",
+                format_bytes(alloc.peak_bytes),
+                alloc.allocations,
+                format_bytes(alloc.allocated_bytes)
+            ));
+        }
+        if let Some((baseline, comparison)) = &self.baseline {
+            summary.push_str(&format!(
+                ", {:+.1}% against {:?} at {} (z = {:.2})",
+                comparison.change * 100.0,
+                baseline.median(),
+                baseline.commit,
+                comparison.z
+            ));
+            match comparison.verdict {
+                Verdict::Regression => summary.push_str(" REGRESSION"),
+                Verdict::Improvement => summary.push_str(" faster"),
+                Verdict::Unchanged => {}
+            }
+        }
+        summary
+    }
+
+    pub fn to_json(&self) -> String {
+        let record = &self.record;
+        let mut fields = vec![
+            format!("\"day\": {}", record.day),
+            format!("\"part\": {}", record.part),
+            format!("\"input\": {}", json_string(&record.input)),
+            format!("\"input_hash\": {}", json_string(&record.input_hash)),
+            format!("\"commit\": {}", json_string(&record.commit)),
+            format!("\"status\": \"{}\"", self.status_name()),
+            match self.status {
+                RunStatus::Finished => format!("\"median_ns\": {}", record.median().as_nanos()),
+                _ => "\"median_ns\": null".to_string(),
+            },
+            format!(
+                "\"samples_ns\": [{}]",
+                record
+                    .samples_ns
+                    .iter()
+                    .map(|ns| ns.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        ];
+        let json_or_null = |value: Option<u64>| value.map_or("null".to_string(), |v| v.to_string());
+        fields.push(format!(
+            "\"peak_bytes\": {}",
+            json_or_null(self.alloc.map(|a| a.peak_bytes))
+        ));
+        fields.push(format!(
+            "\"allocations\": {}",
+            json_or_null(self.alloc.map(|a| a.allocations))
+        ));
+        fields.push(format!(
+            "\"allocated_bytes\": {}",
+            json_or_null(self.alloc.map(|a| a.allocated_bytes))
+        ));
+        if let RunStatus::Panicked(message) = &self.status {
+            fields.push(format!("\"error\": {}", json_string(message)));
+        }
+        if let Some((baseline, comparison)) = &self.baseline {
+            fields.push(format!(
+                "\"baseline\": {{\"commit\": {}, \"median_ns\": {}, \"change\": {:.4}, \"z\": {:.3}, \"verdict\": \"{:?}\"}}",
+                json_string(&baseline.commit),
+                baseline.median().as_nanos(),
+                comparison.change,
+                comparison.z,
+                comparison.verdict
+            ));
+        }
+        format!("{{{}}}", fields.join(", "))
+    }
+
+    fn status_name(&self) -> &'static str {
+        match self.status {
+            RunStatus::Finished => "finished",
+            RunStatus::TimedOut(_) => "timed_out",
+            RunStatus::Panicked(_) => "panicked",
+        }
+    }
+}
+
+/// One JSON object per result, in an array.
+pub fn results_to_json(results: &[BenchResult]) -> String {
+    let objects: Vec<String> = results
+        .iter()
+        .map(|result| format!("  {}", result.to_json()))
+        .collect();
+    format!("[\n{}\n]", objects.join(",\n"))
+}
+
+fn json_string(s: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+fn record(commit: &str, samples_ns: &[u64]) -> BenchRecord {
+    BenchRecord {
+        commit: commit.to_string(),
+        recorded_at: 1_700_000_000,
+        day: 4,
+        part: 2,
+        input: "day04.txt".to_string(),
+        input_hash: input_hash(&["..@".to_string()]),
+        samples_ns: samples_ns.to_vec(),
+    }
+}
+
+#[test]
+fn test_history_lines_round_trip() {
+    let first = record("abc1234", &[120, 100, 110]);
+    assert_eq!(
+        BenchRecord::from_line(&first.to_line()),
+        Some(first.clone())
+    );
+    assert_eq!(first.median(), Duration::from_nanos(110));
+    assert_eq!(BenchRecord::from_line("not a record"), None);
+
+    let history = vec![first, record("def5678", &[1])];
+    assert_eq!(
+        find_baseline(&history, &record("def5678", &[2])),
+        Some(&history[0])
+    );
+}
+
+#[test]
+fn test_compare() {
+    let base: Vec<u64> = (0..10).map(|i| 1000 + i * 10).collect();
+    let slower: Vec<u64> = base.iter().map(|ns| ns * 2).collect();
+    let noisy: Vec<u64> = base.iter().map(|ns| ns + 5).collect();
+
+    let comparison = compare(&record("a", &base), &record("b", &slower), 0.1);
+    assert_eq!(comparison.verdict, Verdict::Regression);
+    assert!(comparison.z > 3.0);
+    assert_eq!(
+        compare(&record("b", &slower), &record("c", &base), 0.1).verdict,
+        Verdict::Improvement
+    );
+    // Slower by a hair, and still within the threshold.
+    assert_eq!(
+        compare(&record("a", &base), &record("b", &noisy), 0.1).verdict,
+        Verdict::Unchanged
+    );
+    assert_eq!(mann_whitney_z(&base, &base), 0.0);
+}
+
+#[test]
+fn test_result_json() {
+    let result = BenchResult {
+        record: record("abc\"1", &[10, 30, 20]),
+        status: RunStatus::Finished,
+        alloc: Some(AllocStats {
+            peak_bytes: 2048,
+            allocations: 3,
+            allocated_bytes: 4096,
+        }),
+        baseline: None,
+    };
+    assert_eq!(
+        result.to_json(),
+        format!(
+            "{{\"day\": 4, \"part\": 2, \"input\": \"day04.txt\", \"input_hash\": \"{}\", \
+             \"commit\": \"abc\\\"1\", \"status\": \"finished\", \"median_ns\": 20, \"samples_ns\": [10, 30, 20], \
+             \"peak_bytes\": 2048, \"allocations\": 3, \"allocated_bytes\": 4096}}",
+            result.record.input_hash
+        )
+    );
+    assert!(result.summary().ends_with(
+        "median 20ns over 3 runs, peak heap 2.0 KiB, 3 allocations of 4.0 KiB in total"
+    ));
+}
+
+#[test]
+fn test_unfinished_results() {
+    let timed_out = BenchResult {
+        record: record("abc1234", &[]),
+        status: RunStatus::TimedOut(Duration::from_secs(2)),
+        alloc: None,
+        baseline: None,
+    };
+    assert_eq!(
+        timed_out.summary(),
+        "Day 4 part 2 on day04.txt: timed out after 2s"
+    );
+    assert!(
+        timed_out
+            .to_json()
+            .contains("\"status\": \"timed_out\", \"median_ns\": null, \"samples_ns\": []")
+    );
+
+    let panicked = BenchResult {
+        status: RunStatus::Panicked("bad input".to_string()),
+        ..timed_out
+    };
+    assert_eq!(
+        panicked.summary(),
+        "Day 4 part 2 on day04.txt: panicked: bad input"
+    );
+    assert!(panicked.to_json().contains("\"error\": \"bad input\""));
+}
@@ -0,0 +1,93 @@
+use crate::args::JoltageSolver;
+use crate::days;
+use crate::utils::rng::Rng;
+use std::ops::Range;
+use std::panic::{AssertUnwindSafe, catch_unwind};
+
+/// A generated input on which the fast and the naive solver give different answers.
+#[derive(Debug)]
+pub struct Disagreement {
+    pub day: u8,
+    pub part: u8,
+    pub seed: u64,
+    pub lines: Vec<String>,
+    pub fast: String,
+    pub naive: String,
+}
+
+/// Runs a solver, turning a panic into an answer so it shows up as a disagreement.
+pub fn answer_or_panic(solver: impl FnOnce() -> String) -> String {
+    catch_unwind(AssertUnwindSafe(solver)).unwrap_or_else(|panic| {
+        let message = panic
+            .downcast_ref::<String>()
+            .cloned()
+            .or_else(|| panic.downcast_ref::<&str>().map(|s| s.to_string()))
+            .unwrap_or_default();
+        format!("panic: {}", message)
+    })
+}
+
+/// Days the puzzle defines a part for; day 12 only has one.
+pub fn parts(day: u8) -> &'static [u8] {
+    if day == 12 { &[1] } else { &[1, 2] }
+}
+
+/// Generates an input for every seed and day and compares both parts of the fast and the
+/// naive solvers on it, stopping at the first difference. Day 10's joltages are solved
+/// with `joltage_solver`.
+pub fn find_disagreement(
+    days: &[u8],
+    seeds: Range<u64>,
+    size: usize,
+    joltage_solver: JoltageSolver,
+) -> Option<Disagreement> {
+    for seed in seeds {
+        for &day in days {
+            let lines: Vec<String> = days::generate(day, &mut Rng::new(seed), size)
+                .into_iter()
+                .filter(|line| !line.is_empty())
+                .collect();
+            for &part in parts(day) {
+                let fast = answer_or_panic(|| match day {
+                    10 => days::day10::solve_with(part, &lines, joltage_solver),
+                    _ => days::solve(day, part, &lines),
+                });
+                let naive = answer_or_panic(|| days::solve_naive(day, part, &lines));
+                if fast != naive {
+                    return Some(Disagreement {
+                        day,
+                        part,
+                        seed,
+                        lines,
+                        fast,
+                        naive,
+                    });
+                }
+            }
+        }
+    }
+    None
+}
+
+#[test]
+fn test_fast_and_naive_solvers_agree() {
+    let days: Vec<u8> = (1..=12).collect();
+    let lp = find_disagreement(&[10], 0..3, 8, JoltageSolver::Lp);
+    if let Some(d) = find_disagreement(&days, 0..3, 8, JoltageSolver::Exact).or(lp) {
+        panic!(
+            "Day {} part {} seed {}: fast {} != naive {}\n{}",
+            d.day,
+            d.part,
+            d.seed,
+            d.fast,
+            d.naive,
+            d.lines.join("\n")
+        );
+    }
+}
+
+#[test]
+fn test_answer_or_panic() {
+    assert_eq!(answer_or_panic(|| "42".to_string()), "42");
+    assert_eq!(answer_or_panic(|| panic!("boom {}", 1)), "panic: boom 1");
+}
@@ -0,0 +1,267 @@
+use crate::args::Failure;
+use crate::days;
+use crate::tools::differential::answer_or_panic;
+use itertools::Itertools;
+
+/// How the failure shows up on these lines, or `None` if it doesn't: the message of the
+/// first part that panics, or the first part the fast and naive solvers disagree on.
+/// Shrinking keeps candidates that fail the same way, so it can't drift to another bug.
+pub fn failure_on(day: u8, parts: &[u8], failure: Failure, lines: &[String]) -> Option<String> {
+    parts.iter().find_map(|&part| {
+        let fast = answer_or_panic(|| days::solve(day, part, lines));
+        match failure {
+            Failure::Panic => fast.starts_with("panic: ").then_some(fast),
+            Failure::Disagreement => (fast
+                != answer_or_panic(|| days::solve_naive(day, part, lines)))
+            .then(|| format!("part {} disagrees", part)),
+        }
+    })
+}
+
+/// The lines the solvers see, as input files are read without their blank lines. Failures
+/// have to be looked for on these, not on the raw file.
+pub fn solver_lines(lines: &[String]) -> Vec<String> {
+    lines.iter().filter(|l| !l.is_empty()).cloned().collect()
+}
+
+/// Shrinks `lines` while `holds` keeps returning true for them: delta debugging over whole
+/// lines first, then the day's own reductions, repeated until neither finds anything smaller.
+/// `holds` has to be true for the solver lines of the original.
+pub fn minimize(
+    day: u8,
+    lines: &[String],
+    holds: &mut dyn FnMut(&[String]) -> bool,
+) -> Vec<String> {
+    let mut current = solver_lines(lines);
+    loop {
+        current = delta_debug(current, holds);
+        if day == 2 {
+            current = delta_debug_items(current, holds);
+        }
+        match day_reductions(day, &current)
+            .into_iter()
+            .find(|candidate| holds(candidate))
+        {
+            Some(smaller) => current = smaller,
+            None => return current,
+        }
+    }
+}
+
+/// Zeller's ddmin: try removing chunks of the lines, each time keeping the complement if
+/// the predicate still holds, and halve the chunk size once no chunk can go.
+fn delta_debug(mut lines: Vec<String>, holds: &mut dyn FnMut(&[String]) -> bool) -> Vec<String> {
+    let mut chunks = 2;
+    while lines.len() >= 2 {
+        let chunk_size = lines.len().div_ceil(chunks);
+        let reduced = (0..lines.len()).step_by(chunk_size).find_map(|start| {
+            let without: Vec<String> = lines[..start]
+                .iter()
+                .chain(lines.get(start + chunk_size..).unwrap_or_default())
+                .cloned()
+                .collect();
+            holds(&without).then_some(without)
+        });
+        match reduced {
+            Some(without) => {
+                lines = without;
+                chunks = (chunks - 1).max(2);
+            }
+            None if chunk_size == 1 => break,
+            None => chunks = (chunks * 2).min(lines.len()),
+        }
+    }
+    lines
+}
+
+/// Delta debugging over the comma-separated items of every line, for inputs that are a
+/// single long line like day 2's ranges.
+fn delta_debug_items(
+    mut lines: Vec<String>,
+    holds: &mut dyn FnMut(&[String]) -> bool,
+) -> Vec<String> {
+    for i in 0..lines.len() {
+        let items: Vec<String> = lines[i].split(',').map(String::from).collect();
+        let mut candidate = lines.clone();
+        let items = delta_debug(items, &mut |items| {
+            candidate[i] = items.join(",");
+            holds(&candidate)
+        });
+        lines[i] = items.join(",");
+    }
+    lines
+}
+
+/// Smaller variants of an input that dropping whole lines can't reach, most aggressive first.
+fn day_reductions(day: u8, lines: &[String]) -> Vec<Vec<String>> {
+    match day {
+        2 | 5 => shorten_ranges(lines),
+        4 | 7 => drop_grid_columns(lines),
+        11 => drop_graph_edges(lines),
+        _ => Vec::new(),
+    }
+}
+
+/// Every `start-end` range cut down to one end, or to either half.
+fn shorten_ranges(lines: &[String]) -> Vec<Vec<String>> {
+    let mut candidates = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        let ranges: Vec<&str> = line.split(',').collect();
+        for (j, range) in ranges.iter().enumerate() {
+            let Some((start, end)) = range.trim().split_once('-') else {
+                continue;
+            };
+            let (Ok(start), Ok(end)) = (start.parse::<i64>(), end.parse::<i64>()) else {
+                continue;
+            };
+            if start >= end {
+                continue;
+            }
+            let middle = start + (end - start) / 2;
+            for (new_start, new_end) in [
+                (start, start),
+                (end, end),
+                (start, middle),
+                (middle + 1, end),
+            ] {
+                let mut shortened = ranges.clone();
+                let replacement = format!("{}-{}", new_start, new_end);
+                shortened[j] = &replacement;
+                let mut candidate = lines.to_vec();
+                candidate[i] = shortened.join(",");
+                candidates.push(candidate);
+            }
+        }
+    }
+    candidates
+}
+
+/// The grid without one of its columns; rows go with the line-level delta debugging.
+fn drop_grid_columns(lines: &[String]) -> Vec<Vec<String>> {
+    let width = lines.iter().map(|line| line.len()).max().unwrap_or(0);
+    if width <= 1 {
+        return Vec::new();
+    }
+    (0..width)
+        .map(|column| {
+            lines
+                .iter()
+                .map(|line| {
+                    line.char_indices()
+                        .filter(|&(i, _)| i != column)
+                        .map(|(_, c)| c)
+                        .collect()
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// The graph without one of its `node: outputs...` edges.
+fn drop_graph_edges(lines: &[String]) -> Vec<Vec<String>> {
+    let mut candidates = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        let Some((node, outputs)) = line.split_once(':') else {
+            continue;
+        };
+        let outputs: Vec<&str> = outputs.split_whitespace().collect();
+        for dropped in 0..outputs.len() {
+            let mut candidate = lines.to_vec();
+            candidate[i] = format!(
+                "{}:{}",
+                node,
+                outputs
+                    .iter()
+                    .enumerate()
+                    .filter(|&(j, _)| j != dropped)
+                    .map(|(_, output)| format!(" {}", output))
+                    .join("")
+            );
+            candidates.push(candidate);
+        }
+    }
+    candidates
+}
+
+#[cfg(test)]
+fn strings(lines: &[&str]) -> Vec<String> {
+    lines.iter().map(|line| line.to_string()).collect()
+}
+
+#[test]
+fn test_delta_debug_finds_the_needed_lines() {
+    let lines: Vec<String> = (0..40).map(|i| format!("R{}", i)).collect();
+    let mut runs = 0;
+    let minimal = minimize(1, &lines, &mut |lines| {
+        runs += 1;
+        lines.contains(&"R7".to_string()) && lines.contains(&"R31".to_string())
+    });
+    assert_eq!(minimal, strings(&["R7", "R31"]));
+    assert!(runs < 100, "{} predicate runs", runs);
+}
+
+#[test]
+fn test_day_reductions() {
+    // A single roll of paper is the smallest grid with an accessible roll.
+    let grid = strings(&["..@@.", "@@@.@", ".@@@."]);
+    let minimal = minimize(4, &grid, &mut |lines| {
+        answer_or_panic(|| days::solve(4, 1, lines))
+            .parse::<usize>()
+            .is_ok_and(|rolls| rolls > 0)
+    });
+    assert_eq!(minimal, strings(&["@"]));
+
+    let ranges = strings(&["3-5", "10-14", "", "5", "11", "17"]);
+    let minimal = minimize(5, &ranges, &mut |lines| {
+        answer_or_panic(|| days::solve(5, 1, lines)) == "2"
+    });
+    assert_eq!(minimal, strings(&["5-5", "11-11", "5", "11"]));
+
+    // Every range is on the one line, so only dropping ranges from it gets rid of them.
+    let ranges = strings(&["11-22,95-115,998-1012,1188511880-1188511890,222220-222224"]);
+    let minimal = minimize(2, &ranges, &mut |lines| {
+        answer_or_panic(|| days::solve(2, 1, lines))
+            .parse::<u64>()
+            .is_ok_and(|sum| sum > 1_000_000)
+    });
+    assert_eq!(minimal, strings(&["1188511885-1188511885"]));
+
+    let graph = strings(&["you: aaa bbb", "aaa: out", "bbb: out ccc", "ccc: out"]);
+    let minimal = minimize(11, &graph, &mut |lines| {
+        lines.iter().any(|line| line.starts_with("you:"))
+            && answer_or_panic(|| days::solve(11, 1, lines)) == "1"
+    });
+    assert_eq!(minimal, strings(&["you: aaa", "aaa: out"]));
+}
+
+#[test]
+fn test_minimize_keeps_the_same_panic() {
+    let graph = strings(&[
+        "you: aaa ccc",
+        "aaa: bbb out",
+        "bbb: aaa",
+        "ccc: ddd",
+        "ddd:",
+    ]);
+    let original = failure_on(11, &[1], Failure::Panic, &graph).unwrap();
+    assert!(original.contains("cycles"), "{}", original);
+
+    // Dropping the `you` line also panics, but about the missing node instead.
+    let minimal = minimize(11, &graph, &mut |lines| {
+        failure_on(11, &[1], Failure::Panic, lines).as_ref() == Some(&original)
+    });
+    assert_eq!(minimal, strings(&["aaa: bbb", "bbb: aaa"]));
+}
+
+#[test]
+fn test_valid_input_has_nothing_to_minimize() {
+    use crate::utils::input::{get_input_path, read_raw_lines};
+
+    // The blank line between the ranges and the IDs is not an ID that fails to parse.
+    let lines = read_raw_lines(&get_input_path(5, Some("test")));
+    assert!(lines.iter().any(|line| line.is_empty()));
+    assert_eq!(
+        failure_on(5, &[1, 2], Failure::Panic, &solver_lines(&lines)),
+        None
+    );
+}
@@ -0,0 +1,4 @@
+pub mod alloc;
+pub mod bench;
+pub mod differential;
+pub mod minimize;
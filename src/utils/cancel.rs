@@ -0,0 +1,137 @@
+use std::any::Any;
+use std::cell::RefCell;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::time::Duration;
+
+/// Flag shared between a run and whoever gave it a time limit. Long loops call
+/// [`checkpoint`], which stops the run on the current thread once the flag is set.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+/// Panic payload of a run stopped at a checkpoint.
+#[derive(Debug)]
+pub struct Cancelled;
+
+thread_local! {
+    static CURRENT: RefCell<Option<CancelToken>> = const { RefCell::new(None) };
+}
+
+/// Unwinds out of the current run if its token was cancelled; does nothing on threads
+/// without a token, so solvers can call it unconditionally.
+pub fn checkpoint() {
+    let cancelled = CURRENT.with(|current| {
+        current
+            .borrow()
+            .as_ref()
+            .is_some_and(CancelToken::is_cancelled)
+    });
+    if cancelled {
+        std::panic::panic_any(Cancelled);
+    }
+}
+
+/// How a run under [`run_with_timeout`] ended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome<T> {
+    Finished(T),
+    TimedOut,
+    Panicked(String),
+}
+
+/// Runs `f` on its own thread with a fresh token, cancelling it after `timeout`. A run that
+/// never reaches a checkpoint is left behind rather than waited for.
+pub fn run_with_timeout<T: Send + 'static>(
+    timeout: Option<Duration>,
+    f: impl FnOnce() -> T + Send + 'static,
+) -> Outcome<T> {
+    let token = CancelToken::new();
+    let (sender, receiver) = mpsc::channel();
+    let worker_token = token.clone();
+    std::thread::spawn(move || {
+        CURRENT.with(|current| *current.borrow_mut() = Some(worker_token));
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(f));
+        let _ = sender.send(result);
+    });
+
+    let result = match timeout {
+        Some(timeout) => receiver.recv_timeout(timeout).ok(),
+        None => receiver.recv().ok(),
+    };
+    match result {
+        Some(Ok(value)) => Outcome::Finished(value),
+        Some(Err(payload)) if !payload.is::<Cancelled>() => {
+            Outcome::Panicked(panic_message(payload.as_ref()))
+        }
+        _ => {
+            token.cancel();
+            Outcome::TimedOut
+        }
+    }
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    payload
+        .downcast_ref::<String>()
+        .cloned()
+        .or_else(|| payload.downcast_ref::<&str>().map(|s| s.to_string()))
+        .unwrap_or_default()
+}
+
+/// Keeps the panic hook quiet about runs stopped at a checkpoint; other panics are still
+/// reported as before.
+pub fn silence_cancellations() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        if !info.payload().is::<Cancelled>() {
+            default_hook(info);
+        }
+    }));
+}
+
+#[test]
+fn test_run_with_timeout() {
+    assert_eq!(
+        run_with_timeout(Some(Duration::from_secs(10)), || 6 * 7),
+        Outcome::Finished(42)
+    );
+    assert_eq!(
+        run_with_timeout(None, || -> u8 { panic!("boom") }),
+        Outcome::Panicked("boom".to_string())
+    );
+
+    // Reports being dropped, which only happens once the loop below unwinds.
+    struct DropSignal(mpsc::Sender<()>);
+    impl Drop for DropSignal {
+        fn drop(&mut self) {
+            let _ = self.0.send(());
+        }
+    }
+    let (sender, dropped) = mpsc::channel();
+    let outcome = run_with_timeout(Some(Duration::from_millis(20)), move || {
+        let _signal = DropSignal(sender);
+        loop {
+            checkpoint();
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    });
+    assert_eq!(outcome, Outcome::<()>::TimedOut);
+    assert!(dropped.recv_timeout(Duration::from_secs(10)).is_ok());
+}
@@ -1,15 +1,24 @@
 use log::info;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 pub fn get_repo_root() -> PathBuf {
     PathBuf::from(env!("CARGO_MANIFEST_DIR"))
 }
 
 pub fn get_input_path<S: AsRef<str>>(day: u32, part: Option<S>) -> PathBuf {
+    get_inputs_file(day, part, "txt")
+}
+
+/// Expected answers for an input live next to it, e.g. `day01_test.answers`.
+pub fn get_answers_path<S: AsRef<str>>(day: u32, part: Option<S>) -> PathBuf {
+    get_inputs_file(day, part, "answers")
+}
+
+fn get_inputs_file<S: AsRef<str>>(day: u32, part: Option<S>, extension: &str) -> PathBuf {
     let option_part = part.map(|p| format!("_{}", p.as_ref())).unwrap_or_default();
     let mut path = get_repo_root();
     path.push("inputs");
-    path.push(format!("day{:02}{}.txt", day, option_part));
+    path.push(format!("day{:02}{}.{}", day, option_part, extension));
 
     path
 }
@@ -17,12 +26,42 @@ pub fn get_input_path<S: AsRef<str>>(day: u32, part: Option<S>) -> PathBuf {
 pub fn read_input_lines<S: AsRef<str>>(day: u32, part: Option<S>) -> Vec<String> {
     let input_path = get_input_path(day, part);
     info!("Reading inputs from {}", input_path.display());
-    std::fs::read_to_string(&input_path)
+    read_raw_lines(&input_path)
+        .into_iter()
+        .filter(|line| !line.is_empty())
+        .collect()
+}
+
+/// Every line of the file, empty ones included, so that indices map to line numbers.
+pub fn read_raw_lines(path: &Path) -> Vec<String> {
+    std::fs::read_to_string(path)
         .unwrap_or_else(|e| {
-            panic!("Unable to read input file at {:?}: {e}", input_path);
+            panic!("Unable to read input file at {:?}: {e}", path);
         })
         .lines()
         .map(String::from)
-        .filter(|line| !line.is_empty())
+        .collect()
+}
+
+/// Reads `part<N>: <answer>` lines from the answers file next to an input.
+pub fn read_expected_answers<S: AsRef<str>>(day: u32, part: Option<S>) -> Vec<(u8, String)> {
+    let answers_path = get_answers_path(day, part);
+    std::fs::read_to_string(&answers_path)
+        .unwrap_or_else(|e| {
+            panic!("Unable to read answers file at {:?}: {e}", answers_path);
+        })
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let (part, answer) = line
+                .split_once(':')
+                .unwrap_or_else(|| panic!("Invalid answer line: {}", line));
+            let part = part
+                .trim()
+                .strip_prefix("part")
+                .and_then(|p| p.parse().ok())
+                .unwrap_or_else(|| panic!("Invalid part in answer line: {}", line));
+            (part, answer.trim().to_string())
+        })
         .collect()
 }
@@ -1,3 +1,10 @@
+pub mod cancel;
 pub mod input;
 pub mod integers;
 pub mod point;
+pub mod ppm;
+#[cfg(test)]
+pub mod property;
+pub mod rng;
+pub mod svg;
+pub mod validate;
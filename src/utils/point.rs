@@ -159,6 +159,40 @@ fn test_point_reverse() {
     assert_eq!(walked, expected);
 }
 
+#[test]
+fn test_walk_to_takes_manhattan_distance_steps() {
+    use crate::utils::property::check;
+    use itertools::Itertools;
+    check(
+        "walk_to steps once per unit of distance and ends at the target",
+        500,
+        |g| {
+            let mut point = || Point {
+                x: g.range(0..=40) as usize,
+                y: g.range(0..=40) as usize,
+            };
+            (point(), point())
+        },
+        |(from, to)| {
+            let walked: Vec<Point> = from.walk_to(to).collect();
+            if walked.len() != from.manhattan_distance(to) {
+                return Err(format!("{} points walked", walked.len()));
+            }
+            if walked.last().unwrap_or(from) != to {
+                return Err(format!("walk ends at {:?}", walked.last()));
+            }
+            match std::iter::once(from)
+                .chain(&walked)
+                .tuple_windows()
+                .find(|(a, b)| a.manhattan_distance(b) != 1)
+            {
+                Some((a, b)) => Err(format!("jump from {:?} to {:?}", a, b)),
+                None => Ok(()),
+            }
+        },
+    );
+}
+
 #[test]
 fn test_adjacent_neighbours() {
     let p = Point { x: 1, y: 1 };
@@ -192,3 +226,26 @@ fn test_from_to_mod() {
     let p_converted = Point::from_u64_mod(value, 100);
     assert_eq!(p, p_converted);
 }
+
+#[test]
+fn test_from_u64_mod_undoes_to_u64_mod() {
+    crate::utils::property::check(
+        "from_u64_mod undoes to_u64_mod",
+        500,
+        |g| {
+            let x_mod = g.range(1..=1_000_000) as usize;
+            let x = g.range(0..=x_mod as i64 - 1) as usize;
+            (
+                Point {
+                    x,
+                    y: g.range(0..=1_000_000) as usize,
+                },
+                x_mod,
+            )
+        },
+        |&(p, x_mod)| match Point::from_u64_mod(p.to_u64_mod(x_mod), x_mod) {
+            round_trip if round_trip == p => Ok(()),
+            round_trip => Err(format!("came back as {:?}", round_trip)),
+        },
+    );
+}
@@ -0,0 +1,40 @@
+use itertools::Itertools;
+
+pub type Rgb = (u8, u8, u8);
+
+/// A plain (P3) PPM image where every pixel is `scale`x`scale` screen pixels.
+pub fn plain_ppm(
+    width: usize,
+    height: usize,
+    scale: usize,
+    pixel: impl Fn(usize, usize) -> Rgb,
+) -> String {
+    let mut ppm = format!("P3\n{} {}\n255\n", width * scale, height * scale);
+    for y in 0..height * scale {
+        let row = (0..width * scale)
+            .map(|x| {
+                let (r, g, b) = pixel(x / scale, y / scale);
+                format!("{} {} {}", r, g, b)
+            })
+            .join("  ");
+        ppm.push_str(&row);
+        ppm.push('\n');
+    }
+    ppm
+}
+
+#[test]
+fn test_plain_ppm() {
+    let ppm = plain_ppm(
+        2,
+        1,
+        2,
+        |x, _| if x == 0 { (0, 0, 0) } else { (255, 10, 1) },
+    );
+    assert_eq!(
+        ppm,
+        "P3\n4 2\n255\n\
+         0 0 0  0 0 0  255 10 1  255 10 1\n\
+         0 0 0  0 0 0  255 10 1  255 10 1\n"
+    );
+}
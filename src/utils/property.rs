@@ -0,0 +1,151 @@
+use crate::utils::rng::Rng;
+use std::fmt::Debug;
+use std::ops::RangeInclusive;
+
+/// Source of random values for property tests. Every value drawn is recorded as a choice,
+/// so a failing case can be replayed from smaller choices, which gives smaller values.
+pub struct Gen {
+    rng: Option<Rng>,
+    choices: Vec<u64>,
+    used: usize,
+}
+
+impl Gen {
+    fn random(seed: u64) -> Self {
+        Gen {
+            rng: Some(Rng::new(seed)),
+            choices: Vec::new(),
+            used: 0,
+        }
+    }
+
+    /// Replays recorded choices; once they run out every draw is the smallest value.
+    fn replay(choices: Vec<u64>) -> Self {
+        Gen {
+            rng: None,
+            choices,
+            used: 0,
+        }
+    }
+
+    /// Uniform value in `0..bound` when random, the recorded choice capped to fit otherwise.
+    pub fn below(&mut self, bound: u64) -> u64 {
+        assert!(bound > 0, "Empty range");
+        let value = match (&mut self.rng, self.choices.get(self.used)) {
+            (Some(rng), _) => {
+                let value = rng.below(bound);
+                self.choices.push(value);
+                value
+            }
+            (None, Some(&choice)) => choice.min(bound - 1),
+            (None, None) => 0,
+        };
+        self.used += 1;
+        value
+    }
+
+    /// Value in the range; shrinks towards its start.
+    pub fn range(&mut self, range: RangeInclusive<i64>) -> i64 {
+        let (start, end) = range.into_inner();
+        assert!(start <= end, "Empty range {}..={}", start, end);
+        start + self.below((end - start) as u64 + 1) as i64
+    }
+
+    /// Shrinks towards `false`.
+    pub fn bool(&mut self) -> bool {
+        self.below(2) == 1
+    }
+
+    /// Up to `max_len` items; shrinks towards fewer and smaller ones.
+    pub fn vec<T>(&mut self, max_len: usize, mut item: impl FnMut(&mut Gen) -> T) -> Vec<T> {
+        let len = self.below(max_len as u64 + 1);
+        (0..len).map(|_| item(self)).collect()
+    }
+}
+
+/// Runs `property` on `runs` generated values and panics with the smallest failing value
+/// found by shrinking the choices behind the first failure.
+pub fn check<T: Debug>(
+    name: &str,
+    runs: u64,
+    generate: impl Fn(&mut Gen) -> T,
+    property: impl Fn(&T) -> Result<(), String>,
+) {
+    for seed in 0..runs {
+        let mut random = Gen::random(seed);
+        let value = generate(&mut random);
+        let Err(message) = property(&value) else {
+            continue;
+        };
+
+        let fails = |choices: &[u64]| {
+            let mut replay = Gen::replay(choices.to_vec());
+            let value = generate(&mut replay);
+            let used = choices.len().min(replay.used);
+            property(&value)
+                .err()
+                .map(|message| (choices[..used].to_vec(), value, message))
+        };
+        let mut smallest = (random.choices, value, message);
+        while let Some(smaller) = shrink_once(&smallest.0, &fails) {
+            smallest = smaller;
+        }
+        panic!(
+            "Property '{}' failed on seed {}: {}\nSmallest failing value: {:?}",
+            name, seed, smallest.2, smallest.1
+        );
+    }
+}
+
+/// One step towards simpler choices that still fail: dropping a run of choices, or making
+/// a single one smaller. Returns the used choices, value and message of the smaller case.
+fn shrink_once<T>(
+    choices: &[u64],
+    fails: &impl Fn(&[u64]) -> Option<(Vec<u64>, T, String)>,
+) -> Option<(Vec<u64>, T, String)> {
+    for chunk in [8, 4, 2, 1] {
+        for start in (0..choices.len().saturating_sub(chunk - 1)).rev() {
+            let without = [&choices[..start], &choices[start + chunk..]].concat();
+            if let Some(smaller) = fails(&without) {
+                return Some(smaller);
+            }
+        }
+    }
+    for i in 0..choices.len() {
+        let choice = choices[i];
+        for smaller in [0, choice / 2, choice.saturating_sub(1)] {
+            if smaller >= choice {
+                continue;
+            }
+            let mut candidate = choices.to_vec();
+            candidate[i] = smaller;
+            if let Some(smaller) = fails(&candidate) {
+                return Some(smaller);
+            }
+        }
+    }
+    None
+}
+
+#[test]
+fn test_check_shrinks_failures() {
+    let panic = std::panic::catch_unwind(|| {
+        check(
+            "sums stay small",
+            100,
+            |g| g.vec(20, |g| g.range(0..=1000)),
+            |values: &Vec<i64>| {
+                let sum: i64 = values.iter().sum();
+                if sum < 1000 {
+                    Ok(())
+                } else {
+                    Err(format!("sum is {}", sum))
+                }
+            },
+        )
+    })
+    .unwrap_err();
+    let message = panic.downcast_ref::<String>().unwrap();
+    // Any smaller value would no longer add up to 1000.
+    assert!(message.contains(": sum is 1000\n"), "{}", message);
+}
@@ -0,0 +1,92 @@
+use std::fmt::Write;
+use std::path::Path;
+
+/// A minimal SVG document builder, elements are drawn in the order they are added.
+pub struct Svg {
+    width: f64,
+    height: f64,
+    elements: Vec<String>,
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+impl Svg {
+    pub fn new(width: f64, height: f64) -> Self {
+        Svg {
+            width,
+            height,
+            elements: Vec::new(),
+        }
+    }
+
+    /// Adds a rectangle; `title` becomes a tooltip in browsers.
+    pub fn rect(
+        &mut self,
+        x: f64,
+        y: f64,
+        width: f64,
+        height: f64,
+        style: &str,
+        title: Option<&str>,
+    ) {
+        let title = title
+            .map(|t| format!("<title>{}</title>", escape(t)))
+            .unwrap_or_default();
+        self.elements.push(format!(
+            r#"<rect x="{x:.2}" y="{y:.2}" width="{width:.2}" height="{height:.2}" style="{style}">{title}</rect>"#
+        ));
+    }
+
+    pub fn polygon(&mut self, points: &[(f64, f64)], style: &str) {
+        let points = points.iter().fold(String::new(), |mut acc, (x, y)| {
+            let _ = write!(acc, "{x:.2},{y:.2} ");
+            acc
+        });
+        self.elements.push(format!(
+            r#"<polygon points="{}" style="{style}"/>"#,
+            points.trim_end()
+        ));
+    }
+
+    pub fn text(&mut self, x: f64, y: f64, text: &str, style: &str) {
+        self.elements.push(format!(
+            r#"<text x="{x:.2}" y="{y:.2}" style="{style}">{}</text>"#,
+            escape(text)
+        ));
+    }
+
+    pub fn render(&self) -> String {
+        let mut out = format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w:.0}" height="{h:.0}" viewBox="0 0 {w:.2} {h:.2}">"#,
+            w = self.width,
+            h = self.height
+        );
+        out.push('\n');
+        for element in &self.elements {
+            out.push_str(element);
+            out.push('\n');
+        }
+        out.push_str("</svg>\n");
+        out
+    }
+
+    pub fn write_to(&self, path: &Path) -> std::io::Result<()> {
+        std::fs::write(path, self.render())
+    }
+}
+
+#[test]
+fn test_svg_render() {
+    let mut svg = Svg::new(10.0, 20.0);
+    svg.rect(1.0, 2.0, 3.0, 4.0, "fill:red", Some("a < b"));
+    svg.text(0.0, 5.0, "x & y", "font-size:3px");
+    let rendered = svg.render();
+    assert!(rendered.starts_with("<svg "));
+    assert!(rendered.contains(r#"<rect x="1.00" y="2.00" width="3.00" height="4.00" style="fill:red"><title>a &lt; b</title></rect>"#));
+    assert!(rendered.contains(">x &amp; y</text>"));
+    assert!(rendered.ends_with("</svg>\n"));
+}
@@ -0,0 +1,107 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+/// Something wrong with an input, found before any solver gets to panic on it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Problem {
+    /// 1-based line number, or `None` when the problem is about the input as a whole.
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl Problem {
+    pub fn at(line: usize, message: impl Into<String>) -> Self {
+        Problem {
+            line: Some(line),
+            message: message.into(),
+        }
+    }
+
+    pub fn input(message: impl Into<String>) -> Self {
+        Problem {
+            line: None,
+            message: message.into(),
+        }
+    }
+}
+
+impl Display for Problem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "line {}: {}", line, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+/// Non-empty lines with their 1-based line numbers; the solvers skip the empty ones too.
+pub fn numbered(lines: &[String]) -> Vec<(usize, &str)> {
+    lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| !line.is_empty())
+        .map(|(i, line)| (i + 1, line.as_str()))
+        .collect()
+}
+
+/// Parses `text` or records why it isn't a `what` on `line`.
+pub fn parse<T: FromStr>(
+    line: usize,
+    text: &str,
+    what: &str,
+    problems: &mut Vec<Problem>,
+) -> Option<T> {
+    let parsed = text.trim().parse().ok();
+    if parsed.is_none() {
+        problems.push(Problem::at(line, format!("'{}' is not {}", text, what)));
+    }
+    parsed
+}
+
+/// Checks that the rows form a non-empty rectangle of the `allowed` characters.
+pub fn check_grid(rows: &[(usize, &str)], allowed: &str) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    let Some(&(_, first)) = rows.first() else {
+        problems.push(Problem::input("The grid is empty"));
+        return problems;
+    };
+    let width = first.chars().count();
+    for &(line, row) in rows {
+        if row.chars().count() != width {
+            problems.push(Problem::at(
+                line,
+                format!(
+                    "Row is {} cells wide, the first row is {}",
+                    row.chars().count(),
+                    width
+                ),
+            ));
+        }
+        for (column, c) in row.chars().enumerate() {
+            if !allowed.contains(c) {
+                problems.push(Problem::at(
+                    line,
+                    format!("Invalid cell character '{}' in column {}", c, column + 1),
+                ));
+            }
+        }
+    }
+    problems
+}
+
+#[test]
+fn test_check_grid() {
+    let lines: Vec<String> = ["..@", "", ".x@", "@."].map(String::from).to_vec();
+    let problems: Vec<String> = check_grid(&numbered(&lines), ".@")
+        .iter()
+        .map(|p| p.to_string())
+        .collect();
+    assert_eq!(
+        problems,
+        [
+            "line 3: Invalid cell character 'x' in column 2",
+            "line 4: Row is 2 cells wide, the first row is 3",
+        ]
+    );
+    assert_eq!(check_grid(&[], ".")[0].to_string(), "The grid is empty");
+}
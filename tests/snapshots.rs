@@ -0,0 +1,161 @@
+//! Golden-file tests of everything the binary prints for each example in `inputs/`.
+//!
+//! Every `inputs/dayNN_<tag>.answers` file lists the parts to run on that example, and the
+//! stdout of `aoc-2025 --day N --part P --input-tag <tag>` is compared with
+//! `tests/snapshots/dayNN_<tag>_partP.snap`. On a mismatch the new output is written next to
+//! the snapshot as `.snap.new` for review; `cargo test --test snapshots -- --bless` accepts
+//! every new output.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+struct Case {
+    name: String,
+    args: Vec<String>,
+}
+
+/// One case per part listed in each answers file, in file name order.
+fn cases(inputs: &Path) -> Vec<Case> {
+    let mut answers: Vec<PathBuf> = fs::read_dir(inputs)
+        .expect("Unable to list the inputs directory")
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "answers"))
+        .collect();
+    answers.sort();
+
+    let mut cases = Vec::new();
+    for path in answers {
+        let stem = path.file_stem().unwrap().to_str().unwrap();
+        let (day, tag) = stem.split_once('_').expect("Answers should be dayNN_<tag>");
+        let day: u8 = day.trim_start_matches("day").parse().unwrap();
+        for line in fs::read_to_string(&path).unwrap().lines() {
+            let Some((part, _)) = line.split_once(':') else {
+                continue;
+            };
+            let part = part.trim().trim_start_matches("part");
+            let mut args: Vec<String> = [
+                "--day",
+                &day.to_string(),
+                "--part",
+                part,
+                "--input-tag",
+                tag,
+            ]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect();
+            if day == 10 {
+                // lpsolve writes its own log to stdout, which says nothing about this crate.
+                args.extend(["--joltage-solver".to_string(), "exact".to_string()]);
+            }
+            cases.push(Case {
+                name: format!("{}_part{}", stem, part),
+                args,
+            });
+        }
+    }
+    cases
+}
+
+/// Drops the parts of the output that change from run to run: log timestamps and the
+/// checkout location.
+fn normalize(stdout: &str, repo: &str) -> String {
+    stdout
+        .lines()
+        .map(|line| {
+            let line = match line.split_once(' ') {
+                Some((stamp, rest)) if stamp.len() == 24 && stamp.as_bytes()[10] == b'T' => rest,
+                _ => line,
+            };
+            format!("{}\n", line.replace(repo, "<repo>"))
+        })
+        .collect()
+}
+
+fn main() {
+    let bless = std::env::args().any(|arg| arg == "--bless");
+    let repo = env!("CARGO_MANIFEST_DIR");
+    let snapshots = Path::new(repo).join("tests").join("snapshots");
+    fs::create_dir_all(&snapshots).unwrap();
+
+    let mut failures = Vec::new();
+    let cases = cases(&Path::new(repo).join("inputs"));
+    for case in &cases {
+        let output = Command::new(env!("CARGO_BIN_EXE_aoc-2025"))
+            .args(&case.args)
+            .output()
+            .expect("Unable to run aoc-2025");
+        let actual = format!(
+            "args: {}\n---\n{}",
+            case.args.join(" "),
+            normalize(&String::from_utf8_lossy(&output.stdout), repo)
+        );
+        let path = snapshots.join(format!("{}.snap", case.name));
+        let new_path = path.with_extension("snap.new");
+        if !output.status.success() {
+            failures.push(format!(
+                "{}: exited with {}\n{}",
+                case.name,
+                output.status,
+                String::from_utf8_lossy(&output.stderr)
+            ));
+            continue;
+        }
+
+        let expected = fs::read_to_string(&path).ok();
+        if expected.as_deref() == Some(actual.as_str()) {
+            let _ = fs::remove_file(&new_path);
+            println!("{} ... ok", case.name);
+        } else if bless {
+            fs::write(&path, &actual).unwrap();
+            let _ = fs::remove_file(&new_path);
+            println!("{} ... blessed", case.name);
+        } else {
+            fs::write(&new_path, &actual).unwrap();
+            failures.push(match expected {
+                None => format!("{}: no snapshot yet, see {}", case.name, new_path.display()),
+                Some(expected) => format!(
+                    "{}: output changed, see {}\n{}",
+                    case.name,
+                    new_path.display(),
+                    first_difference(&expected, &actual)
+                ),
+            });
+        }
+    }
+
+    if failures.is_empty() {
+        println!("{} snapshots match", cases.len());
+        return;
+    }
+    for failure in &failures {
+        eprintln!("{}\n", failure);
+    }
+    eprintln!(
+        "{} of {} snapshots failed; run `cargo test --test snapshots -- --bless` to accept the new output",
+        failures.len(),
+        cases.len()
+    );
+    std::process::exit(1);
+}
+
+fn first_difference(expected: &str, actual: &str) -> String {
+    let mut expected_lines = expected.lines();
+    let mut actual_lines = actual.lines();
+    for line in 1.. {
+        match (expected_lines.next(), actual_lines.next()) {
+            (None, None) => break,
+            (e, a) if e == a => continue,
+            (e, a) => {
+                return format!(
+                    "first difference on line {}:\n- {}\n+ {}",
+                    line,
+                    e.unwrap_or("<end of output>"),
+                    a.unwrap_or("<end of output>")
+                );
+            }
+        }
+    }
+    String::new()
+}